@@ -21,4 +21,30 @@ pub struct Args {
     /// Show dangerous paths, e.g. hidden files and directories used by other apps
     #[arg(long)]
     pub dangerous: bool,
+    /// Also find global caches used by the found projects, e.g. the Go build and module caches
+    #[arg(long)]
+    pub global_caches: bool,
+    /// Offer Cargo build artifacts not rebuilt for at least DAYS days for partial cleaning [default: 30]
+    #[arg(long, value_name = "DAYS")]
+    pub stale_artifacts: Option<u64>,
+    /// Show and sort by apparent file sizes instead of disk usage in interactive mode
+    #[arg(long)]
+    pub apparent_size: bool,
+    /// Only show projects without any activity (changes outside of matched directories) for at least DAYS days
+    #[arg(long, value_name = "DAYS")]
+    pub stale: Option<u64>,
+    /// Scan every directory again instead of reusing unchanged results of the previous scan
+    #[arg(long)]
+    pub no_cache: bool,
+    /// Compare results with the previous scan of the same directory, listing new, removed and grown matches
+    ///
+    /// The scan is saved as the baseline for the next comparison, so it can't be combined with --no-cache.
+    #[arg(long, conflicts_with_all = ["no_ui", "stale", "delete_instantly", "time_limit", "resume", "no_cache"])]
+    pub diff: bool,
+    /// Stop scanning after MINUTES minutes, saving a checkpoint of the remaining directories for --resume
+    #[arg(long, value_name = "MINUTES")]
+    pub time_limit: Option<u64>,
+    /// Continue the scan stopped by --time-limit, together with the matches found before
+    #[arg(long)]
+    pub resume: bool,
 }
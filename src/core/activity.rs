@@ -0,0 +1,130 @@
+use super::{DEFAULT_THREAD_COUNT, _CORE_MULTIPLIER};
+use jwalk::WalkDir;
+use std::{
+    cmp::{max, Reverse},
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    sync::{
+        mpsc::{channel, Sender},
+        Arc,
+    },
+    thread::{self, available_parallelism, JoinHandle},
+    time::SystemTime,
+};
+use tracing::{debug, error, info};
+
+/// Files inside of a `.git` directory which are modified on every commit, checkout, pull etc.
+const GIT_ACTIVITY_FILES: &[&str] = &["HEAD", "FETCH_HEAD", "logs/HEAD"];
+
+/// Returns the number of full days which have passed since `time`.
+pub fn days_since(time: SystemTime) -> Option<u64> {
+    let dur = SystemTime::now().duration_since(time).ok()?;
+    Some(dur.as_secs() / 86400)
+}
+
+/// Returns the time of the last activity in a project (group) directory.
+///
+/// It is the newest modification time of all files in the directory, excluding the `excluded` paths
+/// (which should contain all matches, e.g. build outputs) and `.git` contents, except for its `HEAD` and reflog.
+/// Unlike [`DirStats::last_mod`](super::DirStats::last_mod), it tells whether the project itself is still in use.
+pub fn project_activity(group: &Path, excluded: Arc<HashSet<PathBuf>>) -> Option<SystemTime> {
+    debug!("Calculating project activity for {:?}", group);
+    let files = WalkDir::new(group)
+        .parallelism(jwalk::Parallelism::Serial)
+        .skip_hidden(false)
+        .follow_links(false)
+        .process_read_dir(move |_depth, _path, _state, children| {
+            // The group itself is also filtered here (at depth 0), but it may be excluded as a nested group
+            children.retain(|child| {
+                child.as_ref().map_or(true, |child| {
+                    child.depth == 0 || (child.file_name != ".git" && !excluded.contains(&child.path()))
+                })
+            })
+        });
+
+    let files_activity = files
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type.is_file())
+        .filter_map(|entry| entry.metadata().ok()?.modified().ok());
+    let git = group.join(".git");
+    let git_activity = GIT_ACTIVITY_FILES.iter().filter_map(|file| git.join(file).metadata().ok()?.modified().ok());
+
+    let activity = files_activity.chain(git_activity).max();
+    debug!("Project activity for {:?}: {:?}", group, activity);
+    activity
+}
+
+/// Calculates [`project_activity()`] for every group in a separate pool of threads,
+/// sending results to `tx` along with their group paths.
+///
+/// Every directory is walked only once: groups nested in other groups are excluded from their walks,
+/// and the outer groups are sent only after their activity is updated with the activity of all the nested ones.
+/// Groups which are matches themselves (e.g. shared caches) are not projects, so they are sent without any activity
+/// and not walked at all.
+pub fn project_activity_parallel(
+    groups: Vec<PathBuf>,
+    excluded: Arc<HashSet<PathBuf>>,
+    tx: Sender<(PathBuf, Option<SystemTime>)>,
+) -> Vec<JoinHandle<()>> {
+    let thread_count =
+        max(available_parallelism().map(|x| x.get()).unwrap_or(DEFAULT_THREAD_COUNT) / _CORE_MULTIPLIER, 1);
+    info!("Running project activity with {} threads.", thread_count);
+    let chunk_size = max(groups.len().div_ceil(thread_count), 1);
+
+    let matched: Arc<HashSet<_>> = Arc::new(groups.iter().filter(|group| excluded.contains(*group)).cloned().collect());
+    let all: HashSet<_> = groups.iter().filter(|group| !matched.contains(*group)).map(PathBuf::as_path).collect();
+    let outer: Arc<HashSet<_>> = Arc::new(
+        groups
+            .iter()
+            .flat_map(|group| group.ancestors().skip(1))
+            .filter(|path| all.contains(path))
+            .map(Path::to_path_buf)
+            .collect(),
+    );
+    let excluded: Arc<HashSet<_>> = Arc::new(excluded.iter().chain(&groups).cloned().collect());
+
+    let coordinator = thread::spawn(move || {
+        let (own_tx, own_rx) = channel();
+        for chunk in groups.chunks(chunk_size) {
+            let chunk = chunk.to_vec();
+            let (outer, matched, excluded) = (outer.clone(), matched.clone(), excluded.clone());
+            let (tx, own_tx) = (tx.clone(), own_tx.clone());
+            thread::spawn(move || {
+                for group in chunk {
+                    if matched.contains(&group) {
+                        if tx.send((group, None)).is_err() {
+                            error!("Failed to send");
+                        }
+                        continue;
+                    }
+                    let activity = project_activity(&group, excluded.clone());
+                    if !outer.contains(&group) && tx.send((group.clone(), activity)).is_err() {
+                        error!("Failed to send");
+                    }
+                    let _ = own_tx.send((group, activity));
+                }
+            });
+        }
+        drop(own_tx);
+
+        // Received once all the workers are finished, then propagated from the deepest groups up
+        let mut activity: HashMap<_, _> = own_rx.iter().collect();
+        let mut nested: Vec<_> = activity.keys().cloned().collect();
+        nested.sort_by_key(|group| Reverse(group.components().count()));
+        for group in nested {
+            let Some(parent) = group.ancestors().skip(1).find(|path| activity.contains_key(*path)) else {
+                continue;
+            };
+            let nested_activity = activity[&group];
+            let parent = activity.get_mut(parent).expect("Expected group");
+            *parent = (*parent).max(nested_activity);
+        }
+        for group in outer.iter() {
+            if tx.send((group.clone(), activity[group])).is_err() {
+                error!("Failed to send");
+            }
+        }
+    });
+    vec![coordinator]
+}
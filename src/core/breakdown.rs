@@ -0,0 +1,133 @@
+use super::{inode_registry::ByteSizes, SizeKind};
+use serde::{Deserialize, Serialize};
+use size::Size;
+use std::{
+    cmp::Reverse,
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+/// Depth of the subdirectory tree in a [`Breakdown`], e.g. 2 for `debug/incremental` in a `target` directory.
+const BREAKDOWN_DEPTH: usize = 2;
+
+/// Size of a subdirectory inside of a match, with its own largest subdirectories.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SubdirSize {
+    /// Name of the subdirectory (lossily converted to UTF-8).
+    pub name: String,
+    pub apparent_size: u64,
+    pub disk_size: u64,
+    /// Largest subdirectories, biggest first.
+    pub children: Vec<SubdirSize>,
+}
+
+impl SubdirSize {
+    /// Returns the size of the selected kind.
+    #[inline]
+    pub fn size(&self, kind: SizeKind) -> Size {
+        Size::from_bytes(match kind {
+            SizeKind::Disk => self.disk_size,
+            SizeKind::Apparent => self.apparent_size,
+        })
+    }
+}
+
+/// Total size of files with the same extension inside of a match.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExtensionSize {
+    /// Lowercase extension without the leading dot, [`None`] for files without one.
+    pub extension: Option<String>,
+    pub apparent_size: u64,
+    pub disk_size: u64,
+}
+
+impl ExtensionSize {
+    /// Returns the size of the selected kind.
+    #[inline]
+    pub fn size(&self, kind: SizeKind) -> Size {
+        Size::from_bytes(match kind {
+            SizeKind::Disk => self.disk_size,
+            SizeKind::Apparent => self.apparent_size,
+        })
+    }
+}
+
+/// Largest parts of a match, calculated optionally together with its [`DirStats`](super::DirStats).
+///
+/// Entries are ranked by disk usage. Subdirectories are nested up to two levels deep.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Breakdown {
+    /// Largest subdirectories, biggest first.
+    pub subdirs: Vec<SubdirSize>,
+    /// Largest groups of files by extension, biggest first.
+    pub extensions: Vec<ExtensionSize>,
+}
+
+/// Accumulates sizes of entries found while calculating stats into a [`Breakdown`].
+#[derive(Debug)]
+pub(super) struct BreakdownBuilder {
+    /// Number of entries retained on every level.
+    top: usize,
+    /// Sizes of subdirectories up to [`BREAKDOWN_DEPTH`], by paths relative to the match.
+    subdirs: HashMap<PathBuf, ByteSizes>,
+    extensions: HashMap<Option<String>, ByteSizes>,
+}
+
+impl BreakdownBuilder {
+    pub(super) fn new(top: usize) -> Self {
+        Self {
+            top,
+            subdirs: HashMap::new(),
+            extensions: HashMap::new(),
+        }
+    }
+
+    /// Adds an entry at `relative` path inside of the match to all its parent subdirectories,
+    /// as well as to its extension if it's a file.
+    pub(super) fn add(&mut self, relative: &Path, is_dir: bool, sizes: ByteSizes) {
+        let components: Vec<_> = relative.components().collect();
+        // Files are only counted in their parents, while directories in themselves as well
+        let parents = if is_dir { components.len() } else { components.len().saturating_sub(1) };
+        for depth in 1..=parents.min(BREAKDOWN_DEPTH) {
+            *self.subdirs.entry(components[..depth].iter().collect()).or_default() += sizes;
+        }
+        if !is_dir {
+            let extension = relative.extension().map(|ext| ext.to_string_lossy().to_lowercase());
+            *self.extensions.entry(extension).or_default() += sizes;
+        }
+    }
+
+    pub(super) fn build(self) -> Breakdown {
+        let mut extensions: Vec<_> = self
+            .extensions
+            .into_iter()
+            .map(|(extension, sizes)| ExtensionSize {
+                extension,
+                apparent_size: sizes.apparent,
+                disk_size: sizes.disk,
+            })
+            .collect();
+        extensions.sort_by_key(|ext| Reverse(ext.disk_size));
+        extensions.truncate(self.top);
+
+        Breakdown {
+            subdirs: Self::largest_children(&self.subdirs, Path::new(""), self.top),
+            extensions,
+        }
+    }
+
+    fn largest_children(subdirs: &HashMap<PathBuf, ByteSizes>, parent: &Path, top: usize) -> Vec<SubdirSize> {
+        let mut children: Vec<_> = subdirs.iter().filter(|(path, _)| path.parent() == Some(parent)).collect();
+        children.sort_by_key(|(_, sizes)| Reverse(sizes.disk));
+        children
+            .into_iter()
+            .take(top)
+            .map(|(path, sizes)| SubdirSize {
+                name: path.file_name().unwrap_or_default().to_string_lossy().into_owned(),
+                apparent_size: sizes.apparent,
+                disk_size: sizes.disk,
+                children: Self::largest_children(subdirs, path, top),
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,215 @@
+use super::{
+    scan_index::{load_json, save_json, IndexedMatch},
+    scanner::ScannerCache,
+    Heuristic, MatchData,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    ffi::OsString,
+    fmt, fs,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    time::Instant,
+};
+use tracing::{error, info};
+
+/// Directory which has not been scanned before the time limit was reached,
+/// saved together with the scanner state inherited from its parent.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PendingDir {
+    path: PathBuf,
+    /// Inherited files, by heuristic name.
+    inherited_files: BTreeMap<String, Vec<PathBuf>>,
+    dangerous: bool,
+    marked_to_be_dangerous: Vec<String>,
+}
+
+impl PendingDir {
+    fn new(path: PathBuf, cache: &ScannerCache) -> Self {
+        Self {
+            path,
+            inherited_files: cache
+                .inherited_files
+                .iter()
+                .map(|(name, files)| (name.to_string(), files.clone()))
+                .collect(),
+            dangerous: cache.dangerous,
+            marked_to_be_dangerous: cache
+                .marked_to_be_dangerous
+                .iter()
+                .map(|name| name.to_string_lossy().into_owned())
+                .collect(),
+        }
+    }
+
+    /// Restores the inherited state on top of `template`, looking up heuristic names in the given heuristics.
+    fn restore(&self, template: &ScannerCache, heuristics: &[&'static dyn Heuristic]) -> Option<ScannerCache> {
+        let inherited_files = self
+            .inherited_files
+            .iter()
+            .map(|(name, files)| {
+                let lang = heuristics.iter().map(|heuristic| heuristic.info()).find(|lang| lang.name == name)?;
+                Some((lang.name, files.clone()))
+            })
+            .collect::<Option<_>>()?;
+        Some(ScannerCache {
+            inherited_files,
+            dangerous: self.dangerous,
+            marked_to_be_dangerous: self.marked_to_be_dangerous.iter().map(OsString::from).collect(),
+            ..template.clone()
+        })
+    }
+}
+
+/// Matches found by a stopped scan, and directories left to be scanned with their inherited state.
+type ResumedScan = (Vec<MatchData>, Vec<(PathBuf, ScannerCache)>);
+
+/// State of a scan stopped by its time limit, stored on disk.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Checkpoint {
+    /// Hash of the heuristics and scanner settings used by the stopped scan.
+    fingerprint: u64,
+    /// Matches found before the scan was stopped, including the ones found by previous resumed scans.
+    matches: Vec<IndexedMatch>,
+    /// Directories which have not been scanned yet.
+    pending: Vec<PendingDir>,
+}
+
+/// Time limit of a single scan, shared by all the scanner threads.
+///
+/// Once the deadline passes, directories which are already being scanned are finished, but their
+/// subdirectories are not entered anymore. They are collected instead, together with all the matches
+/// sent so far, and saved as a checkpoint (see [`Self::finish()`]), which the next scan can resume from.
+pub(super) struct CheckpointUpdate {
+    path: Option<PathBuf>,
+    heuristics: Vec<&'static dyn Heuristic>,
+    deadline: Option<Instant>,
+    current: Mutex<Checkpoint>,
+    aborted: AtomicBool,
+}
+
+impl fmt::Debug for CheckpointUpdate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CheckpointUpdate")
+            .field("path", &self.path)
+            .field("deadline", &self.deadline)
+            .finish_non_exhaustive()
+    }
+}
+
+impl CheckpointUpdate {
+    pub(super) fn new(
+        path: Option<PathBuf>,
+        heuristics: Vec<&'static dyn Heuristic>,
+        fingerprint: u64,
+        deadline: Option<Instant>,
+    ) -> Self {
+        Self {
+            path,
+            current: Mutex::new(Checkpoint {
+                fingerprint,
+                ..Checkpoint::default()
+            }),
+            heuristics,
+            deadline,
+            aborted: AtomicBool::new(false),
+        }
+    }
+
+    /// Loads the checkpoint saved by a previous scan, if it was stopped with the same heuristics and settings.
+    ///
+    /// Returns matches found by the previous scan (only those which still exist) and the directories
+    /// which are left to be scanned, with their state restored on top of `template`.
+    pub(super) fn resume(&self, template: &ScannerCache) -> Option<ResumedScan> {
+        let path = self.path.as_ref()?;
+        let checkpoint: Checkpoint = load_json(path)?;
+        if self.current.lock().ok()?.fingerprint != checkpoint.fingerprint {
+            info!("Heuristics or scanner settings have changed, discarding checkpoint {:?}", path);
+            return None;
+        }
+        let matches = checkpoint
+            .matches
+            .iter()
+            .filter(|indexed| indexed.path().exists())
+            .map(|indexed| indexed.restore(&self.heuristics))
+            .collect::<Option<Vec<_>>>()?;
+        let pending = checkpoint
+            .pending
+            .iter()
+            .map(|dir| Some((dir.path.clone(), dir.restore(template, &self.heuristics)?)))
+            .collect::<Option<Vec<_>>>()?;
+        info!(
+            "Resuming scan from checkpoint {:?} with {} matches and {} directories left",
+            path,
+            matches.len(),
+            pending.len()
+        );
+        Some((matches, pending))
+    }
+
+    /// Returns true if the time limit has been reached.
+    #[inline]
+    pub(super) fn expired(&self) -> bool {
+        self.deadline.is_some_and(|deadline| Instant::now() >= deadline)
+    }
+
+    /// Saves a match sent by the scanner, so that it is not lost when the scan is resumed.
+    pub(super) fn record_match(&self, data: &MatchData) {
+        if let (Some(_), Ok(mut current)) = (&self.path, self.current.lock()) {
+            current.matches.push(IndexedMatch::new(data));
+        }
+    }
+
+    /// Saves a directory which will not be scanned because of the time limit,
+    /// together with the `cache` of its parent.
+    pub(super) fn defer(&self, path: PathBuf, cache: &ScannerCache) {
+        if let Ok(mut current) = self.current.lock() {
+            current.pending.push(PendingDir::new(path, cache));
+        }
+    }
+
+    /// Returns true if any directory has been skipped because of the time limit.
+    pub(super) fn stopped(&self) -> bool {
+        self.current.lock().is_ok_and(|current| !current.pending.is_empty())
+    }
+
+    /// Marks the scan as aborted, so that its checkpoint is neither saved nor removed.
+    pub(super) fn abort(&self) {
+        self.aborted.store(true, Ordering::Relaxed);
+    }
+
+    /// Saves the checkpoint if the scan has been stopped, otherwise removes any checkpoint left by previous scans.
+    pub(super) fn finish(&self) {
+        let (Some(path), false) = (&self.path, self.aborted.load(Ordering::Relaxed)) else {
+            return;
+        };
+        let Ok(current) = self.current.lock() else {
+            error!("Checkpoint poisoned, not saving {:?}", path);
+            return;
+        };
+        if current.pending.is_empty() {
+            remove_checkpoint(path);
+            return;
+        }
+        match save_json(&*current, path) {
+            Ok(()) => info!(
+                "Time limit reached, saved checkpoint with {} directories left to {:?}",
+                current.pending.len(),
+                path
+            ),
+            Err(err) => error!("Failed to save checkpoint to {:?}: {}", path, err),
+        }
+    }
+}
+
+fn remove_checkpoint(path: &Path) {
+    match fs::remove_file(path) {
+        Ok(()) => info!("Scan completed, removed checkpoint {:?}", path),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {},
+        Err(err) => error!("Failed to remove checkpoint {:?}: {}", path, err),
+    }
+}
@@ -1,10 +1,12 @@
 use std::{
     cmp::max,
-    fs::{metadata, remove_dir_all, remove_file},
-    path::PathBuf,
+    fs::{self, remove_dir_all, remove_file, symlink_metadata, Permissions},
+    io,
+    path::{Path, PathBuf},
     thread::{self, available_parallelism, JoinHandle},
 };
 
+use jwalk::WalkDir;
 use tracing::{error, info, trace};
 
 use crate::core::{DEFAULT_THREAD_COUNT, _CORE_MULTIPLIER};
@@ -21,17 +23,64 @@ pub fn dir_rm_parallel(data: Vec<PathBuf>) -> Vec<JoinHandle<()>> {
         .map(|chunk| {
             thread::spawn(move || {
                 for ele in chunk {
-                    if let Ok(data) = metadata(ele.clone()) {
-                        if data.is_dir() {
-                            if remove_dir_all(&ele).is_err() {
-                                error!("Failed to remove {:?} as dir.", ele);
-                            }
-                        } else if remove_file(&ele).is_err() {
-                            error!("Failed to remove {:?} as file.", ele);
-                        };
+                    if let Err(err) = remove_path(&ele) {
+                        error!("Failed to remove {:?}: {}", ele, err);
                     }
                 }
             })
         })
         .collect()
 }
+
+/// Removes a file, a symlink (without following it) or a directory with all its contents.
+///
+/// Read-only directory trees, like the Go module cache, are made writable if they can't be removed otherwise.
+/// Paths which do not exist anymore are skipped.
+pub fn remove_path(path: &Path) -> io::Result<()> {
+    let metadata = match symlink_metadata(path) {
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(()),
+        metadata => metadata?,
+    };
+    if !metadata.is_dir() {
+        return remove_file(path);
+    }
+    match remove_dir_all(path) {
+        Err(err) if err.kind() == io::ErrorKind::PermissionDenied => {
+            info!("Permission denied while removing {:?}, making it writable", path);
+            make_tree_writable(path);
+            remove_dir_all(path)
+        },
+        result => result,
+    }
+}
+
+/// Makes all the entries of a directory tree writable by their owner.
+fn make_tree_writable(path: &Path) {
+    let entries = WalkDir::new(path).parallelism(jwalk::Parallelism::Serial).skip_hidden(false).follow_links(false);
+    for entry in entries.into_iter().filter_map(Result::ok) {
+        if entry.file_type.is_symlink() {
+            continue;
+        }
+        let path = entry.path();
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        let mut permissions = metadata.permissions();
+        make_writable(&mut permissions);
+        if let Err(err) = fs::set_permissions(&path, permissions) {
+            error!("Failed to make {:?} writable: {}", path, err);
+        }
+    }
+}
+
+#[cfg(unix)]
+fn make_writable(permissions: &mut Permissions) {
+    use std::os::unix::fs::PermissionsExt;
+    permissions.set_mode(permissions.mode() | 0o700);
+}
+
+#[cfg(not(unix))]
+#[allow(clippy::permissions_set_readonly_false)]
+fn make_writable(permissions: &mut Permissions) {
+    permissions.set_readonly(false);
+}
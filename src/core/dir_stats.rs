@@ -3,27 +3,64 @@ use jwalk::WalkDir;
 use size::Size;
 use std::{
     cmp::{max, Ordering},
-    collections::HashSet,
+    collections::{HashMap, HashSet},
+    fs::Metadata,
     iter::Sum,
     ops::Add,
-    path::PathBuf,
-    sync::mpsc::Sender,
-    thread::{self, available_parallelism, JoinHandle},
+    path::{Path, PathBuf},
+    sync::{
+        mpsc::{channel, Sender},
+        Arc, Mutex,
+    },
+    thread::{self, available_parallelism},
     time::SystemTime,
 };
-use tracing::{debug, error, info, trace};
+use tracing::{debug, error, info, trace, warn};
 
-use crate::core::{DEFAULT_THREAD_COUNT, _CORE_MULTIPLIER};
+use crate::core::{
+    breakdown::BreakdownBuilder,
+    days_since,
+    inode_registry::{ByteSizes, LinkedInode},
+    scan_index::IndexUpdate,
+    Breakdown, InodeRegistry, DEFAULT_THREAD_COUNT, _CORE_MULTIPLIER,
+};
+
+/// Selects which of the sizes in [`DirStats`] should be displayed and used for sorting.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum SizeKind {
+    /// Space allocated on disk, which is roughly what deleting a directory frees.
+    #[default]
+    Disk,
+    /// Sum of file lengths, as reported by most file managers.
+    ///
+    /// May differ a lot from the disk usage for sparse files, compressed filesystems and many small files.
+    Apparent,
+}
 
-#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub struct DirStats {
-    pub size: Option<Size>,
+    /// Sum of apparent file sizes (lengths).
+    pub apparent_size: Option<Size>,
+    /// Sum of sizes allocated on disk, based on the number of used blocks.
+    pub disk_size: Option<Size>,
+    /// Number of files and other non-directory entries.
+    pub files: u64,
+    /// Number of subdirectories, not including the directory itself.
+    pub dirs: u64,
     pub last_mod: Option<SystemTime>,
+    /// Number of files and directories which could not be read while calculating stats.
+    ///
+    /// If it is not zero, all the other values should be treated as lower bounds, see [`Self::is_incomplete()`].
+    pub errors: u64,
+    /// Largest subdirectories and file extensions, only if requested (see [`Self::with_breakdown()`]).
+    ///
+    /// Never kept when adding stats of different directories together.
+    pub breakdown: Option<Breakdown>,
 }
 
 impl Ord for DirStats {
     fn cmp(&self, other: &Self) -> Ordering {
-        match self.size.cmp(&other.size) {
+        match self.disk_size.cmp(&other.disk_size).then(self.apparent_size.cmp(&other.apparent_size)) {
             Ordering::Equal => self.last_mod.cmp(&other.last_mod),
             x => x.reverse(),
         }
@@ -40,13 +77,29 @@ impl Add for DirStats {
     type Output = DirStats;
 
     fn add(self, rhs: Self) -> Self::Output {
-        let size = match (self.size, rhs.size) {
+        self + &rhs
+    }
+}
+
+impl Add<&DirStats> for DirStats {
+    type Output = DirStats;
+
+    fn add(self, rhs: &DirStats) -> Self::Output {
+        let add_sizes = |a: Option<Size>, b: Option<Size>| match (a, b) {
             (Some(a), Some(b)) => Some(a + b),
             (a, b) => a.or(b),
         };
 
         let last_mod = [self.last_mod, rhs.last_mod].iter().flatten().max().copied();
-        DirStats { size, last_mod }
+        DirStats {
+            apparent_size: add_sizes(self.apparent_size, rhs.apparent_size),
+            disk_size: add_sizes(self.disk_size, rhs.disk_size),
+            files: self.files + rhs.files,
+            dirs: self.dirs + rhs.dirs,
+            last_mod,
+            errors: self.errors + rhs.errors,
+            breakdown: None,
+        }
     }
 }
 
@@ -56,71 +109,248 @@ impl Sum for DirStats {
     }
 }
 
+impl<'a> Sum<&'a DirStats> for DirStats {
+    fn sum<I: Iterator<Item = &'a DirStats>>(iter: I) -> Self {
+        iter.fold(DirStats::default(), |prev, current| prev + current)
+    }
+}
+
 impl DirStats {
     pub fn new(path: PathBuf) -> Self {
+        Self::calculate(path, None, None)
+    }
+
+    /// Calculates stats like [`Self::new()`], additionally retaining `top` largest subdirectories
+    /// and file extensions inside of the directory in [`Self::breakdown`].
+    pub fn with_breakdown(path: PathBuf, top: usize) -> Self {
+        Self::calculate(path, None, Some(top))
+    }
+
+    /// Calculates stats like [`Self::new()`] and saves hardlinked files found in the process to `registry`.
+    ///
+    /// This allows checking how much space is shared between multiple matches afterwards.
+    pub fn new_registered(path: PathBuf, registry: &InodeRegistry) -> Self {
+        Self::calculate(path, Some(registry), None)
+    }
+
+    pub(super) fn calculate(path: PathBuf, registry: Option<&InodeRegistry>, breakdown: Option<usize>) -> Self {
         debug!("Calculating stats for {:?}", path);
-        let files_iter = WalkDir::new(path.clone())
+        let files_iter = match WalkDir::new(path.clone())
             .parallelism(jwalk::Parallelism::Serial)
             .skip_hidden(false)
             .follow_links(false)
             .try_into_iter()
-            .map_err(|err| error!("WalkDir into iter error: {:?}", err))
-            .ok()
-            .map(|list| list.filter_map(|ele| ele.ok()).filter(|f| f.metadata().is_ok_and(|f| !f.is_symlink())));
-
-        if let Some(files_iter) = files_iter {
-            let mut max_value: Option<SystemTime> = None;
-            let mut sum_value: Option<u64> = None;
-            let mut visited: HashSet<FileId> = HashSet::default();
-            for file in files_iter {
-                let last_mod = file.metadata().ok().and_then(|m| m.modified().ok());
-                let size = file.metadata().ok().map(|m| m.len());
-                if visited.insert(file_id::get_file_id(file.path()).unwrap()) {
-                    // inode ids needs to be unique
-                    if let Some(size) = size {
-                        sum_value = Some(if let Some(v) = sum_value { v + size } else { size })
+        {
+            Ok(files_iter) => files_iter,
+            Err(err) => {
+                error!("WalkDir into iter error: {:?}", err);
+                return Self {
+                    errors: 1,
+                    ..Self::default()
+                };
+            },
+        };
+
+        let mut max_value: Option<SystemTime> = None;
+        let mut apparent_sum: Option<u64> = None;
+        let mut disk_sum: Option<u64> = None;
+        let (mut files, mut dirs, mut errors) = (0, 0, 0);
+        let mut visited: HashSet<FileId> = HashSet::default();
+        let mut unlinked = ByteSizes::default();
+        let mut linked: HashMap<FileId, LinkedInode> = HashMap::default();
+        let mut builder = breakdown.map(BreakdownBuilder::new);
+        for entry in files_iter {
+            let (file, metadata) = match entry.and_then(|f| f.metadata().map(|m| (f, m))) {
+                Ok(pair) => pair,
+                Err(err) => {
+                    warn!("Failed to read an entry while calculating stats for {:?}: {}", path, err);
+                    errors += 1;
+                    continue;
+                },
+            };
+            if metadata.is_symlink() {
+                continue;
+            }
+
+            let last_mod = metadata.modified().ok();
+            let sizes = ByteSizes {
+                apparent: metadata.len(),
+                disk: allocated_size(&metadata),
+            };
+            let nlink = if metadata.is_dir() { 1 } else { link_count(&metadata) };
+            let file_id = unique_id(&file.path(), &metadata)
+                .map_err(|err| warn!("Failed to get id of {:?}, it may be counted twice: {}", file.path(), err))
+                .ok();
+            if let Some(file_id) = file_id.filter(|_| nlink > 1) {
+                linked
+                    .entry(file_id)
+                    .or_insert(LinkedInode {
+                        sizes,
+                        nlink,
+                        found: 0,
+                    })
+                    .found += 1;
+            }
+            // inode ids needs to be unique
+            if file_id.map_or(true, |id| visited.insert(id)) {
+                apparent_sum = Some(apparent_sum.unwrap_or(0) + sizes.apparent);
+                disk_sum = Some(disk_sum.unwrap_or(0) + sizes.disk);
+                if nlink <= 1 || file_id.is_none() {
+                    unlinked += sizes;
+                }
+                if !metadata.is_dir() {
+                    files += 1;
+                } else if file.depth > 0 {
+                    dirs += 1;
+                }
+                if let Some(builder) = builder.as_mut().filter(|_| file.depth > 0) {
+                    if let Ok(relative) = file.path().strip_prefix(&path) {
+                        builder.add(relative, metadata.is_dir(), sizes);
                     }
                 }
-
-                max_value = if max_value < last_mod { last_mod } else { max_value };
             }
 
-            debug!("Stats for {:?}: size: {:?}, last_mod: {:?}", path, sum_value, max_value);
-            Self { size: sum_value.map(Size::from_bytes), last_mod: max_value }
-        } else {
-            error!("Got empty iterator for {:?}", path);
-            Self { size: None, last_mod: None }
+            max_value = if max_value < last_mod { last_mod } else { max_value };
+        }
+
+        debug!(
+            "Stats for {:?}: size: {:?}, disk size: {:?}, files: {}, dirs: {}, last_mod: {:?}, errors: {}",
+            path, apparent_sum, disk_sum, files, dirs, max_value, errors
+        );
+        if let Some(registry) = registry {
+            registry.register(&path, unlinked, linked);
+        }
+        Self {
+            apparent_size: apparent_sum.map(Size::from_bytes),
+            disk_size: disk_sum.map(Size::from_bytes),
+            files,
+            dirs,
+            last_mod: max_value,
+            errors,
+            breakdown: builder.map(BreakdownBuilder::build),
+        }
+    }
+
+    /// Returns true if some parts of the directory could not be read,
+    /// so all the sizes and counts are only lower bounds.
+    #[inline]
+    pub fn is_incomplete(&self) -> bool {
+        self.errors > 0
+    }
+
+    /// Returns the size of the selected kind.
+    #[inline]
+    pub fn size(&self, kind: SizeKind) -> Option<Size> {
+        match kind {
+            SizeKind::Disk => self.disk_size,
+            SizeKind::Apparent => self.apparent_size,
         }
     }
 
     pub fn last_mod_days(&self) -> Option<u64> {
-        let timestamp = self.last_mod?;
-        let now = SystemTime::now();
-        let dur = now.duration_since(timestamp).ok()?;
-        Some(dur.as_secs() / 86400)
+        days_since(self.last_mod?)
     }
 }
 
-pub fn dir_stats_parallel(data: Vec<(usize, PathBuf)>, tx: Sender<(usize, DirStats)>) -> Vec<JoinHandle<()>> {
-    let thread_count = available_parallelism().map(|x| x.get()).unwrap_or(DEFAULT_THREAD_COUNT) / _CORE_MULTIPLIER;
-    info!("Running dir stats with {} threads.", thread_count);
-    let chunk_size = max(data.len() / thread_count, data.len());
-    let chunks: Vec<_> = data.chunks(chunk_size).map(|s| s.to_vec()).collect();
-    trace!("Chunks: {:?}", chunks.iter().map(|c| c.len()).collect::<Vec<_>>());
-
-    chunks
-        .into_iter()
-        .map(|chunk| {
-            let tx = tx.clone();
-            thread::spawn(move || {
-                for (i, ele) in chunk {
-                    let stats = DirStats::new(ele);
-                    let res = tx.send((i, stats));
-                    if res.is_err() {
-                        error!("Failed to send");
-                    }
+/// Returns the newest modification time of the directory at `path` and all its subdirectories.
+///
+/// Adding, removing or renaming any entry inside of the directory changes it, so it is used to check whether
+/// previously calculated stats are still valid. Returns [`None`] if any of the subdirectories could not be read.
+pub(super) fn tree_mtime(path: &Path) -> Option<SystemTime> {
+    let dirs = WalkDir::new(path)
+        .parallelism(jwalk::Parallelism::Serial)
+        .skip_hidden(false)
+        .follow_links(false)
+        .process_read_dir(|_depth, _path, _state, children| {
+            children.retain(|child| child.as_ref().map_or(true, |child| child.file_type.is_dir()))
+        });
+    let mtimes = dirs.into_iter().map(|entry| entry.ok()?.metadata().ok()?.modified().ok());
+    mtimes.collect::<Option<Vec<_>>>()?.into_iter().max()
+}
+
+/// Returns the number of bytes allocated for a file, based on the number of 512-byte blocks it uses.
+#[cfg(unix)]
+pub(super) fn allocated_size(metadata: &Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    metadata.blocks() * 512
+}
+
+/// Allocated size is not exposed on this platform, so the apparent size is used instead.
+#[cfg(not(unix))]
+pub(super) fn allocated_size(metadata: &Metadata) -> u64 {
+    metadata.len()
+}
+
+/// Returns an identifier of a file, unique within the whole system.
+#[cfg(unix)]
+fn unique_id(_path: &Path, metadata: &Metadata) -> std::io::Result<FileId> {
+    use std::os::unix::fs::MetadataExt;
+    Ok(FileId::new_inode(metadata.dev(), metadata.ino()))
+}
+
+/// Returns an identifier of a file, unique within the whole system.
+#[cfg(not(unix))]
+fn unique_id(path: &Path, _metadata: &Metadata) -> std::io::Result<FileId> {
+    file_id::get_file_id(path)
+}
+
+/// Returns the number of hardlinks to a file.
+#[cfg(unix)]
+fn link_count(metadata: &Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    metadata.nlink()
+}
+
+/// Hardlink count is not exposed on this platform, so every file is treated as having only one.
+#[cfg(not(unix))]
+fn link_count(_metadata: &Metadata) -> u64 {
+    1
+}
+
+/// Spawns a pool of threads calculating [`DirStats`] for every path sent to the returned channel.
+///
+/// Results are sent to `tx` together with their paths, in order of completion.
+/// If `registry` is given, hardlinks found in every match are saved there.
+/// If `index` is given, stats of unmodified matches are reused from it and new ones are saved there.
+/// If `breakdown` is given, that many largest subdirectories and extensions are retained, see [`Breakdown`].
+/// Worker threads exit once the returned sender and all of its clones are dropped.
+/// Each of them releases the index before `tx`, so it is already saved once all the results are received.
+pub(super) fn dir_stats_pool(
+    tx: Sender<(PathBuf, DirStats)>,
+    registry: Option<Arc<InodeRegistry>>,
+    index: Option<Arc<IndexUpdate>>,
+    breakdown: Option<usize>,
+) -> Sender<PathBuf> {
+    let thread_count =
+        max(available_parallelism().map(|x| x.get()).unwrap_or(DEFAULT_THREAD_COUNT) / _CORE_MULTIPLIER, 1);
+    info!("Starting dir stats pool with {} threads.", thread_count);
+    let (queue_tx, queue_rx) = channel::<PathBuf>();
+    let queue_rx = Arc::new(Mutex::new(queue_rx));
+
+    for _ in 0..thread_count {
+        let queue_rx = queue_rx.clone();
+        let tx = tx.clone();
+        let registry = registry.clone();
+        let index = index.clone();
+        thread::spawn(move || {
+            loop {
+                // The lock has to be released before calculating stats, hence the separate statement
+                let next = queue_rx.lock().ok().and_then(|queue| queue.recv().ok());
+                let Some(path) = next else {
+                    trace!("Dir stats queue closed, stopping worker.");
+                    break;
+                };
+                let stats = match &index {
+                    Some(index) => index.dir_stats(path.clone(), registry.as_deref(), breakdown),
+                    None => DirStats::calculate(path.clone(), registry.as_deref(), breakdown),
+                };
+                if tx.send((path, stats)).is_err() {
+                    error!("Failed to send");
+                    break;
                 }
-            })
-        })
-        .collect()
+            }
+            drop(index);
+        });
+    }
+    queue_tx
 }
@@ -0,0 +1,238 @@
+use super::SizeKind;
+use file_id::FileId;
+use size::Size;
+use std::{
+    collections::{HashMap, HashSet},
+    ops::AddAssign,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+use tracing::{debug, error};
+
+/// Apparent and on-disk sizes in bytes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(super) struct ByteSizes {
+    pub(super) apparent: u64,
+    pub(super) disk: u64,
+}
+
+impl ByteSizes {
+    #[inline]
+    fn get(&self, kind: SizeKind) -> u64 {
+        match kind {
+            SizeKind::Disk => self.disk,
+            SizeKind::Apparent => self.apparent,
+        }
+    }
+}
+
+impl AddAssign for ByteSizes {
+    fn add_assign(&mut self, rhs: Self) {
+        self.apparent += rhs.apparent;
+        self.disk += rhs.disk;
+    }
+}
+
+/// Hardlinked inode found while calculating stats of a single match.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct LinkedInode {
+    pub(super) sizes: ByteSizes,
+    /// Total number of hardlinks to the inode, as reported by the filesystem.
+    pub(super) nlink: u64,
+    /// Number of hardlinks found inside the match.
+    pub(super) found: u64,
+}
+
+/// Hardlinked inode with links found across all registered matches.
+#[derive(Debug, Clone)]
+struct SharedInode {
+    sizes: ByteSizes,
+    nlink: u64,
+    /// Number of hardlinks found in each match, by match id.
+    owners: Vec<(usize, u64)>,
+}
+
+impl SharedInode {
+    /// Returns true if all hardlinks to this inode are inside the given matches,
+    /// so deleting them would actually free the space.
+    fn freed_by(&self, ids: &HashSet<usize>) -> bool {
+        let found: u64 = self.owners.iter().map(|(_, count)| count).sum();
+        found >= self.nlink && self.owners.iter().all(|(id, _)| ids.contains(id))
+    }
+}
+
+/// Sizes of a single match which depend on hardlinks in other matches.
+#[derive(Debug, Clone, Copy)]
+struct MatchSizes {
+    exclusive: Size,
+    shared: Size,
+}
+
+#[derive(Debug, Default)]
+struct RegistryData {
+    /// Match ids, used as indices for [`Self::unlinked`] and [`Self::owned`].
+    ids: HashMap<PathBuf, usize>,
+    /// Sizes of files and directories without any additional hardlinks, by match id.
+    unlinked: Vec<ByteSizes>,
+    /// Inodes with more than one hardlink.
+    linked: HashMap<FileId, SharedInode>,
+    /// Hardlinked inodes found in each match, by match id.
+    owned: Vec<Vec<FileId>>,
+    /// Incremented on every change, see [`InodeRegistry::generation()`].
+    generation: u64,
+    /// Sizes of single matches calculated since the last change, by match id and size kind.
+    cache: HashMap<(usize, SizeKind), MatchSizes>,
+}
+
+impl RegistryData {
+    fn freed(&self, ids: &HashSet<usize>, kind: SizeKind) -> Size {
+        let unlinked: u64 = ids.iter().map(|id| self.unlinked[*id].get(kind)).sum();
+        // Only inodes of the given matches are checked, each of them once
+        let inodes: HashSet<_> = ids.iter().flat_map(|id| &self.owned[*id]).collect();
+        let linked: u64 = inodes
+            .into_iter()
+            .filter_map(|file_id| self.linked.get(file_id))
+            .filter(|inode| inode.freed_by(ids))
+            .map(|inode| inode.sizes.get(kind))
+            .sum();
+        Size::from_bytes(unlinked + linked)
+    }
+
+    fn match_sizes(&mut self, id: usize, kind: SizeKind) -> MatchSizes {
+        if let Some(sizes) = self.cache.get(&(id, kind)) {
+            return *sizes;
+        }
+        let ids = HashSet::from([id]);
+        let shared: u64 = self.owned[id]
+            .iter()
+            .filter_map(|file_id| self.linked.get(file_id))
+            .filter(|inode| !inode.freed_by(&ids))
+            .map(|inode| inode.sizes.get(kind))
+            .sum();
+        let sizes = MatchSizes {
+            exclusive: self.freed(&ids, kind),
+            shared: Size::from_bytes(shared),
+        };
+        self.cache.insert((id, kind), sizes);
+        sizes
+    }
+
+    /// Removes all the hardlinks found in the match with the given id.
+    fn clear(&mut self, id: usize) {
+        for file_id in std::mem::take(&mut self.owned[id]) {
+            if let Some(inode) = self.linked.get_mut(&file_id) {
+                inode.owners.retain(|(owner, _)| *owner != id);
+                if inode.owners.is_empty() {
+                    self.linked.remove(&file_id);
+                }
+            }
+        }
+        self.unlinked[id] = ByteSizes::default();
+        self.generation += 1;
+        self.cache.clear();
+    }
+}
+
+/// Scan-wide registry of hardlinks found in matches.
+///
+/// [`DirStats`](super::DirStats) deduplicates inodes only within a single match, so files hardlinked between
+/// many matches (e.g. by pnpm, cargo or Nix) are counted once for each of them. This registry keeps track of
+/// such files across all matches, in order to tell how much space deleting a set of matches would really free.
+///
+/// Only inodes with more than one hardlink are stored individually, sizes of all the other files are summed up.
+#[derive(Debug, Default)]
+pub struct InodeRegistry {
+    data: Mutex<RegistryData>,
+}
+
+impl InodeRegistry {
+    /// Saves results of a stats calculation for the match at `path`, replacing any previous ones.
+    pub(super) fn register(&self, path: &Path, unlinked: ByteSizes, linked: HashMap<FileId, LinkedInode>) {
+        let Ok(mut data) = self.data.lock() else {
+            error!("Inode registry poisoned, skipping {:?}", path);
+            return;
+        };
+        let id = if let Some(id) = data.ids.get(path).copied() {
+            data.clear(id);
+            id
+        } else {
+            let id = data.unlinked.len();
+            data.ids.insert(path.to_owned(), id);
+            data.unlinked.push(ByteSizes::default());
+            data.owned.push(vec![]);
+            id
+        };
+        data.unlinked[id] = unlinked;
+        data.generation += 1;
+        data.cache.clear();
+
+        debug!("Registering {} hardlinked inodes for {:?}", linked.len(), path);
+        for (file_id, inode) in linked {
+            data.linked
+                .entry(file_id)
+                .or_insert_with(|| SharedInode {
+                    sizes: inode.sizes,
+                    nlink: inode.nlink,
+                    owners: vec![],
+                })
+                .owners
+                .push((id, inode.found));
+            data.owned[id].push(file_id);
+        }
+    }
+
+    /// Removes the match at `path`, e.g. after deleting it, so its hardlinks are no longer shared with other matches.
+    pub fn unregister(&self, path: &Path) {
+        let Ok(mut data) = self.data.lock() else {
+            error!("Inode registry poisoned, skipping {:?}", path);
+            return;
+        };
+        if let Some(id) = data.ids.remove(path) {
+            debug!("Unregistering {:?}", path);
+            data.clear(id);
+        }
+    }
+
+    /// Returns a number which changes whenever any match is registered or unregistered,
+    /// so sizes calculated from the registry can be cached until then.
+    pub fn generation(&self) -> u64 {
+        self.data.lock().map(|data| data.generation).unwrap_or_default()
+    }
+
+    /// Returns how much space would be freed by deleting all the given matches.
+    ///
+    /// Files hardlinked from anywhere outside of the given matches are not counted.
+    /// Matches without calculated stats are skipped.
+    pub fn freed_size<'a, I>(&self, paths: I, kind: SizeKind) -> Size
+    where I: IntoIterator<Item = &'a Path> {
+        let Ok(data) = self.data.lock() else { return Size::from_bytes(0) };
+        let ids = paths.into_iter().filter_map(|path| data.ids.get(path).copied()).collect();
+        data.freed(&ids, kind)
+    }
+
+    /// Returns how much space would be freed by deleting every registered match.
+    pub fn total_freed_size(&self, kind: SizeKind) -> Size {
+        let Ok(data) = self.data.lock() else { return Size::from_bytes(0) };
+        let ids = data.ids.values().copied().collect();
+        data.freed(&ids, kind)
+    }
+
+    /// Returns how much space would be freed by deleting only the match at `path`.
+    ///
+    /// Returns [`None`] if stats for this match have not been registered (yet).
+    pub fn exclusive_size(&self, path: &Path, kind: SizeKind) -> Option<Size> {
+        let mut data = self.data.lock().ok()?;
+        let id = *data.ids.get(path)?;
+        Some(data.match_sizes(id, kind).exclusive)
+    }
+
+    /// Returns the size of files in the match at `path` which are hardlinked from outside of it,
+    /// so they would not be freed by deleting only this match.
+    ///
+    /// Returns [`None`] if stats for this match have not been registered (yet).
+    pub fn shared_size(&self, path: &Path, kind: SizeKind) -> Option<Size> {
+        let mut data = self.data.lock().ok()?;
+        let id = *data.ids.get(path)?;
+        Some(data.match_sizes(id, kind).shared)
+    }
+}
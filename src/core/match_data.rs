@@ -1,4 +1,5 @@
 use super::CommentedLang;
+use serde::{Deserialize, Serialize};
 use std::{
     iter::Sum,
     ops::Add,
@@ -51,6 +52,21 @@ impl MatchData {
     pub fn dangerous(&self) -> bool {
         self.params.dangerous
     }
+
+    /// Returns whether the match lies outside of the [scanned directory](super::Scanner::root),
+    /// e.g. a shared build directory configured by a project inside of it.
+    #[inline]
+    pub fn outside_root(&self) -> bool {
+        self.params.outside_root
+    }
+
+    /// Returns whether the match lies inside of another match, e.g. stale artifacts in a build directory.
+    ///
+    /// Such matches allow cleaning only a part of the other match, so their sizes should not be counted twice.
+    #[inline]
+    pub fn partial(&self) -> bool {
+        self.params.partial
+    }
 }
 
 /// Main match parameters that a heuristic sets, returned from [`add_match()`](super::MatchingState::add_match()).
@@ -65,6 +81,10 @@ pub struct MatchParameters {
     pub(super) dangerous: bool,
     /// Whether the default group path in [`MatchData`] should be overridden.
     pub(super) group_override: GroupOverride,
+    /// Whether the match lies outside of the scanned directory.
+    pub(super) outside_root: bool,
+    /// Whether the match lies inside of another match.
+    pub(super) partial: bool,
 }
 
 impl MatchParameters {
@@ -106,6 +126,13 @@ impl MatchParameters {
         self.group_override = GroupOverride::Override(group);
         self
     }
+
+    /// Marks the newly added match as a part of another match, see [`MatchData::partial()`].
+    #[inline]
+    pub fn partial(&mut self) -> &mut Self {
+        self.partial = true;
+        self
+    }
 }
 
 impl Add for MatchParameters {
@@ -116,6 +143,8 @@ impl Add for MatchParameters {
         self.languages.extend(rhs.languages);
         self.dangerous |= rhs.dangerous;
         self.group_override = self.group_override + rhs.group_override;
+        self.outside_root |= rhs.outside_root;
+        self.partial |= rhs.partial;
         self
     }
 }
@@ -127,7 +156,7 @@ impl Sum for MatchParameters {
 }
 
 /// Represents different verdicts on overriding default group path.
-#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub(super) enum GroupOverride {
     /// No override was specified in any heuristic.
     #[default]
@@ -4,7 +4,6 @@ use super::{
 };
 use regex::Regex;
 use std::{
-    any::Any,
     collections::HashMap,
     ffi::{OsStr, OsString},
     fs::FileType,
@@ -20,6 +19,8 @@ use tracing::{debug, error, info, trace, warn};
 pub struct MatchingState<'entries> {
     /// Optimized storage for current directory contents.
     contents: HashMap<OsString, (&'entries mut scanner::Entry, Vec<MatchParameters>)>,
+    /// Matches of paths which are not direct children of the current directory, see [`Self::add_match_path()`].
+    path_matches: HashMap<PathBuf, Vec<MatchParameters>>,
     /// Path of the current directory.
     parent_path: &'entries Path,
     /// Current heuristic being processed.
@@ -39,6 +40,7 @@ impl<'entries> MatchingState<'entries> {
     ) -> Self {
         Self {
             contents: children.iter_mut().map(|v| (v.file_name.clone(), (v.deref_mut(), vec![]))).collect(),
+            path_matches: HashMap::new(),
             current_heuristic: None,
             cache,
             parent_path: path,
@@ -50,7 +52,12 @@ impl<'entries> MatchingState<'entries> {
     ///
     /// This function filters and reorganizes all collected data in order to send it to the specified channel.
     /// `include_dangerous` changes the behavior of this function to mark paths as dangerous instead of skipping them altogether.
-    pub(super) fn process_collected_data(&mut self, include_dangerous: bool) -> Result<(), SendError<MatchData>> {
+    /// Returns all the matches which have been sent.
+    pub(super) fn process_collected_data(
+        &mut self,
+        include_dangerous: bool,
+    ) -> Result<Vec<MatchData>, SendError<MatchData>> {
+        let mut sent = vec![];
         for (entry_name, (entry, params)) in self.contents.drain() {
             let accumulated_params: MatchParameters = params.into_iter().sum();
             trace!(
@@ -93,11 +100,34 @@ impl<'entries> MatchingState<'entries> {
                     };
                     info!("Positive weight of {}, sending match: {:#?} in {:#?}", pw, entry_name, self.parent_path);
                     debug!("{:#?}", data);
-                    self.cache.sender.as_ref().unwrap().send(data)?;
+                    self.cache.send_match(data.clone())?;
+                    sent.push(data);
                 },
             }
         }
-        Ok(())
+
+        for (path, params) in self.path_matches.drain() {
+            let accumulated_params: MatchParameters = params.into_iter().sum();
+            if accumulated_params.weight <= 0 || !path.exists() {
+                info!("Skipping path match: {:#?} in {:#?}", path, self.parent_path);
+                continue;
+            }
+            let outside_root = self.cache.root.as_deref().is_some_and(|root| !path.starts_with(root));
+            let data = MatchData {
+                path,
+                group: self.parent_path.to_owned(),
+                params: MatchParameters {
+                    dangerous: self.cache.dangerous,
+                    outside_root,
+                    ..accumulated_params
+                },
+            };
+            info!("Sending path match: {:#?} in {:#?}", data.path, self.parent_path);
+            debug!("{:#?}", data);
+            self.cache.send_match(data.clone())?;
+            sent.push(data);
+        }
+        Ok(sent)
     }
 
     /// Returns the path of the current directory.
@@ -108,13 +138,27 @@ impl<'entries> MatchingState<'entries> {
         self.parent_path
     }
 
+    /// Returns the number of days after which build artifacts which have not been rebuilt are considered stale,
+    /// see [`Scanner::stale_artifact_days`](crate::Scanner::stale_artifact_days).
+    #[inline]
+    pub fn stale_artifact_days(&self) -> u64 {
+        self.cache.stale_artifact_days
+    }
+
+    /// Returns true if the path lies outside of the scanned directory, which is how path matches are flagged
+    /// (see [`MatchData::outside_root()`]). Heuristics may use it to mention it in the comments of such matches.
+    #[inline]
+    pub fn is_outside_root(&self, path: &Path) -> bool {
+        self.cache.root.as_deref().is_some_and(|root| !path.starts_with(root))
+    }
+
     /// Returns saved file paths for the current heuristic.
     ///
     /// This vector is inherited from parent directories and propagated while traversing filesystem.
     /// It is used to check for matches in files that are not in the current directory
     /// and/or store additional data for future calls.
     pub fn inherited_files(&mut self) -> &mut Vec<PathBuf> {
-        self.cache.inherited_files.entry(self.current_heuristic.type_id()).or_default()
+        self.cache.inherited_files.entry(self.current_heuristic.unwrap().info().name).or_default()
     }
 
     /// Returns the path of the specified file in the current directory if it exists and is accesible.
@@ -190,4 +234,26 @@ impl<'entries> MatchingState<'entries> {
             self.broken_heuristic_params.as_mut().unwrap()
         }
     }
+
+    /// Adds a match for a path which is not a direct child of the current directory,
+    /// e.g. a build directory configured by a project file in the current directory.
+    ///
+    /// The match is sent only if the path exists and its weight is positive. It is not skipped while scanning,
+    /// and it may even lie outside of the scanned directory (see [`MatchData::outside_root()`]).
+    /// Every path is sent only once per scan, even if multiple directories add it.
+    pub fn add_match_path(&mut self, path: &Path, comment: &str) -> &mut MatchParameters {
+        let new = MatchParameters::new(CommentedLang {
+            lang: self.current_heuristic.unwrap().info(),
+            comment: comment.to_owned(),
+        });
+        trace!(
+            "Added path match {:#?} , {:#?} with {:#?}",
+            self.parent_path,
+            path,
+            self.current_heuristic.unwrap().info().name
+        );
+        let matches = self.path_matches.entry(path.to_owned()).or_default();
+        matches.push(new);
+        matches.last_mut().unwrap()
+    }
 }
@@ -1,10 +1,19 @@
-use std::{any::TypeId, collections::HashMap, path::PathBuf};
+use std::{collections::BTreeMap, path::PathBuf};
+
+mod activity;
+pub use activity::{days_since, project_activity, project_activity_parallel};
+
+mod breakdown;
+pub use breakdown::{Breakdown, ExtensionSize, SubdirSize};
 
 mod dir_rm;
-pub use dir_rm::dir_rm_parallel;
+pub use dir_rm::{dir_rm_parallel, remove_path};
 
 mod dir_stats;
-pub use dir_stats::{dir_stats_parallel, DirStats};
+pub use dir_stats::{DirStats, SizeKind};
+
+mod inode_registry;
+pub use inode_registry::InodeRegistry;
 
 mod match_data;
 pub use match_data::{MatchData, MatchParameters};
@@ -21,14 +30,29 @@ pub use lang::{CommentedLang, Lang};
 mod color;
 pub use color::IconColor;
 
+mod checkpoint;
+
+mod scan_diff;
+pub use scan_diff::{LanguageDiff, MatchGrowth, MatchSummary, ScanDiff};
+
+mod scan_index;
+pub use scan_index::indexed_matches;
+pub(crate) use scan_index::file_mtime;
+
 mod scanner;
 pub use scanner::Scanner;
 
+mod size_estimate;
+pub use size_estimate::SizeEstimate;
+
+mod stable_hasher;
+pub(crate) use stable_hasher::StableHasher;
+
 const DEFAULT_THREAD_COUNT: usize = 1;
 const PERCENTAGE_OF_USED_CORES: usize = 50;
 
 const _CORE_MULTIPLIER: usize = 100 / PERCENTAGE_OF_USED_CORES; // Don't modify this line
 
-/// Type for storing files inherited from parent directories.
+/// Type for storing files inherited from parent directories, keyed by heuristic name.
 /// See [`MatchingState::inherited_files()`].
-type InheritedFiles = HashMap<TypeId, Vec<PathBuf>>;
+type InheritedFiles = BTreeMap<&'static str, Vec<PathBuf>>;
@@ -0,0 +1,134 @@
+use super::{DirStats, MatchData, SizeKind};
+use size::Size;
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, HashMap},
+    path::{Path, PathBuf},
+};
+
+/// Match found by a scan, reduced to the data needed to compare scans with each other.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MatchSummary {
+    pub path: PathBuf,
+    /// Names of the languages (heuristics) which found the match.
+    pub languages: Vec<String>,
+    pub stats: Option<DirStats>,
+}
+
+impl MatchSummary {
+    pub fn new(data: &MatchData, stats: Option<DirStats>) -> Self {
+        Self {
+            path: data.path.clone(),
+            languages: data.languages().iter().map(|lang| lang.name().to_owned()).collect(),
+            stats,
+        }
+    }
+
+    /// Returns the size of the selected kind, if stats have been calculated.
+    #[inline]
+    pub fn size(&self, kind: SizeKind) -> Option<Size> {
+        self.stats.as_ref()?.size(kind)
+    }
+}
+
+/// Match which has grown since the previous scan.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MatchGrowth {
+    pub path: PathBuf,
+    pub before: Size,
+    pub after: Size,
+}
+
+impl MatchGrowth {
+    /// Returns the number of bytes by which the match has grown.
+    #[inline]
+    pub fn bytes(&self) -> i64 {
+        self.after.bytes() - self.before.bytes()
+    }
+
+    /// Returns the growth relative to the previous size, [`None`] if the match was empty before.
+    pub fn percent(&self) -> Option<f64> {
+        let before = self.before.bytes();
+        (before > 0).then(|| self.bytes() as f64 * 100.0 / before as f64)
+    }
+}
+
+/// Differences between two scans for a single language.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LanguageDiff {
+    /// Matches which were not found by the previous scan, biggest first.
+    pub new: Vec<MatchSummary>,
+    /// Matches which are no longer found, biggest first.
+    pub removed: Vec<MatchSummary>,
+    /// Matches which have grown, sorted by the number of bytes, descending.
+    pub grown: Vec<MatchGrowth>,
+}
+
+impl LanguageDiff {
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.new.is_empty() && self.removed.is_empty() && self.grown.is_empty()
+    }
+}
+
+/// Comparison of results of two scans of the same directory, grouped by language.
+///
+/// Matches found by many languages are reported for each of them.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ScanDiff {
+    /// Differences by language name, only for languages with any changes.
+    pub languages: BTreeMap<String, LanguageDiff>,
+}
+
+impl ScanDiff {
+    /// Compares the `current` scan results with the `previous` ones, using sizes of the selected kind.
+    ///
+    /// Growth is only reported for matches with stats available in both scans.
+    pub fn new(previous: &[MatchSummary], current: &[MatchSummary], kind: SizeKind) -> Self {
+        let previous_paths: HashMap<&Path, &MatchSummary> = previous.iter().map(|m| (m.path.as_path(), m)).collect();
+        let current_paths: HashMap<&Path, &MatchSummary> = current.iter().map(|m| (m.path.as_path(), m)).collect();
+
+        let mut diff = Self::default();
+        for summary in current {
+            let Some(before) = previous_paths.get(summary.path.as_path()) else {
+                for lang in &summary.languages {
+                    diff.language(lang).new.push(summary.clone());
+                }
+                continue;
+            };
+            if let (Some(before), Some(after)) = (before.size(kind), summary.size(kind)) {
+                if after > before {
+                    for lang in &summary.languages {
+                        diff.language(lang).grown.push(MatchGrowth {
+                            path: summary.path.clone(),
+                            before,
+                            after,
+                        });
+                    }
+                }
+            }
+        }
+        for summary in previous.iter().filter(|m| !current_paths.contains_key(m.path.as_path())) {
+            for lang in &summary.languages {
+                diff.language(lang).removed.push(summary.clone());
+            }
+        }
+
+        for lang in diff.languages.values_mut() {
+            lang.new.sort_by_key(|m| Reverse(m.size(kind)));
+            lang.removed.sort_by_key(|m| Reverse(m.size(kind)));
+            lang.grown.sort_by_key(|growth| Reverse(growth.bytes()));
+        }
+        diff
+    }
+
+    /// Returns true if both scans found the same matches and none of them has grown.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.languages.is_empty()
+    }
+
+    fn language(&mut self, name: &str) -> &mut LanguageDiff {
+        self.languages.entry(name.to_owned()).or_default()
+    }
+}
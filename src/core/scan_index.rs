@@ -0,0 +1,472 @@
+use super::{
+    dir_stats::tree_mtime, inode_registry::ByteSizes, match_data::GroupOverride, Breakdown, CommentedLang, DirStats,
+    Heuristic, InodeRegistry, MatchData, MatchParameters, MatchSummary, SizeEstimate, StableHasher,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use size::Size;
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt,
+    fs::{self, File},
+    hash::{Hash, Hasher},
+    io::{BufReader, BufWriter},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    time::SystemTime,
+};
+use tracing::{debug, error, info, warn};
+
+/// Match saved in the index, with languages stored by name.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(super) struct IndexedMatch {
+    path: PathBuf,
+    group: PathBuf,
+    weight: i32,
+    dangerous: bool,
+    group_override: GroupOverride,
+    #[serde(default)]
+    outside_root: bool,
+    #[serde(default)]
+    partial: bool,
+    /// Names of the matched languages with their comments.
+    languages: Vec<(String, String)>,
+    /// Modification time of the matched path, so that changes inside of it (e.g. a removed marker file)
+    /// invalidate the match even if the directory containing it is unchanged.
+    #[serde(default)]
+    mtime: Option<SystemTime>,
+}
+
+impl IndexedMatch {
+    pub(super) fn new(data: &MatchData) -> Self {
+        Self {
+            path: data.path.clone(),
+            group: data.group.clone(),
+            weight: data.params.weight,
+            dangerous: data.params.dangerous,
+            group_override: data.params.group_override.clone(),
+            outside_root: data.params.outside_root,
+            partial: data.params.partial,
+            languages: data.languages().iter().map(|lang| (lang.name().to_owned(), lang.comment.clone())).collect(),
+            mtime: mtime(&data.path),
+        }
+    }
+
+    #[inline]
+    pub(super) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Restores the match, looking up its languages in the given heuristics.
+    pub(super) fn restore(&self, heuristics: &[&'static dyn Heuristic]) -> Option<MatchData> {
+        let languages = self
+            .languages
+            .iter()
+            .map(|(name, comment)| {
+                let lang = heuristics.iter().map(|heuristic| heuristic.info()).find(|lang| lang.name == name)?;
+                Some(CommentedLang {
+                    lang,
+                    comment: comment.clone(),
+                })
+            })
+            .collect::<Option<_>>()?;
+        Some(MatchData {
+            path: self.path.clone(),
+            group: self.group.clone(),
+            params: MatchParameters {
+                weight: self.weight,
+                languages,
+                dangerous: self.dangerous,
+                group_override: self.group_override.clone(),
+                outside_root: self.outside_root,
+                partial: self.partial,
+            },
+        })
+    }
+}
+
+/// Directory visited during a scan.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct IndexedDir {
+    /// Modification time of the directory, [`None`] if it should always be scanned again.
+    mtime: Option<SystemTime>,
+    /// Hash of the scanner state inherited from parent directories,
+    /// [`None`] if the directory was not scanned and only holds matches found by its ancestors.
+    state: Option<u64>,
+    /// Matches inside of this directory, or outside of it if found by heuristics in this directory.
+    matches: Vec<IndexedMatch>,
+    /// Files read by the heuristics in this directory, with their modification times.
+    #[serde(default)]
+    files: Vec<(PathBuf, Option<SystemTime>)>,
+}
+
+/// [`DirStats`] of a match, valid as long as no directory inside of the match is modified.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct IndexedStats {
+    /// Newest modification time of the match and all its subdirectories.
+    tree_mtime: SystemTime,
+    apparent_size: Option<u64>,
+    disk_size: Option<u64>,
+    files: u64,
+    dirs: u64,
+    last_mod: Option<SystemTime>,
+    errors: u64,
+    breakdown: Option<Breakdown>,
+}
+
+impl IndexedStats {
+    fn new(tree_mtime: SystemTime, stats: &DirStats) -> Self {
+        Self {
+            tree_mtime,
+            apparent_size: stats.apparent_size.map(|size| size.bytes() as u64),
+            disk_size: stats.disk_size.map(|size| size.bytes() as u64),
+            files: stats.files,
+            dirs: stats.dirs,
+            last_mod: stats.last_mod,
+            errors: stats.errors,
+            breakdown: stats.breakdown.clone(),
+        }
+    }
+
+    fn restore(&self) -> DirStats {
+        DirStats {
+            apparent_size: self.apparent_size.map(Size::from_bytes),
+            disk_size: self.disk_size.map(Size::from_bytes),
+            files: self.files,
+            dirs: self.dirs,
+            last_mod: self.last_mod,
+            errors: self.errors,
+            breakdown: self.breakdown.clone(),
+        }
+    }
+}
+
+/// Results of a previous scan, stored on disk.
+///
+/// All the paths are saved lossily converted to UTF-8, so that the index can always be serialized.
+/// Non UTF-8 paths will then never be found on disk, which forces scanning them again.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ScanIndex {
+    /// Hash of the heuristics and scanner settings used to build this index.
+    fingerprint: u64,
+    /// Visited directories, sorted so that every subtree is a continuous range.
+    dirs: BTreeMap<PathBuf, IndexedDir>,
+    stats: HashMap<PathBuf, IndexedStats>,
+}
+
+impl ScanIndex {
+    /// Returns the entry of a directory, adding one which holds only matches if it was not scanned yet.
+    fn dir_entry(&mut self, path: &Path) -> &mut IndexedDir {
+        self.dirs.entry(index_key(path)).or_insert_with(|| IndexedDir {
+            mtime: mtime(path),
+            state: None,
+            matches: Vec::new(),
+            files: Vec::new(),
+        })
+    }
+
+    /// Returns all the directories whose whole subtree is unchanged since this index was saved.
+    ///
+    /// A directory is unchanged if neither it, its matches nor the files read by the heuristics in it
+    /// have been modified. Directories are checked only once, starting with the deepest ones,
+    /// so that every changed directory invalidates its nearest indexed ancestor.
+    fn unchanged_subtrees(&self) -> HashSet<PathBuf> {
+        let mut changed_inside = HashSet::new();
+        let mut unchanged = HashSet::new();
+        for (dir, indexed) in self.dirs.iter().rev() {
+            let modified = indexed.mtime.is_none()
+                || mtime(dir) != indexed.mtime
+                || indexed
+                    .matches
+                    .iter()
+                    .any(|indexed| indexed.mtime.is_none() || mtime(&indexed.path) != indexed.mtime)
+                || indexed.files.iter().any(|(file, modified)| file_mtime(file) != *modified);
+            if !modified && !changed_inside.contains(dir.as_path()) {
+                unchanged.insert(dir.clone());
+            } else if let Some(parent) = dir.ancestors().skip(1).find(|parent| self.dirs.contains_key(*parent)) {
+                debug!("Directory {:?} changed since the previous scan", dir);
+                changed_inside.insert(parent.to_owned());
+            }
+        }
+        unchanged
+    }
+}
+
+/// Loads a JSON file saved with [`save_json()`], logging any errors.
+pub(super) fn load_json<T: DeserializeOwned>(path: &Path) -> Option<T> {
+    let file = File::open(path).map_err(|err| info!("File {:?} not loaded: {}", path, err)).ok()?;
+    serde_json::from_reader(BufReader::new(file)).map_err(|err| warn!("Invalid JSON in {:?}: {}", path, err)).ok()
+}
+
+/// Saves a value as JSON, creating all the parent directories.
+pub(super) fn save_json<T: Serialize>(value: &T, path: &Path) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    // Write to a temporary file first, so that the file is never left half-written
+    let temporary = path.with_extension(format!("{}.tmp", std::process::id()));
+    let mut writer = BufWriter::new(File::create(&temporary)?);
+    serde_json::to_writer(&mut writer, value)?;
+    writer.into_inner().map_err(|err| err.into_error())?.sync_all()?;
+    fs::rename(temporary, path)
+}
+
+/// Returns a hash of the heuristics and scanner settings, which have to match for saved results to be reused.
+pub(super) fn fingerprint(heuristics: &[&'static dyn Heuristic], dangerous: bool, stale_artifact_days: u64) -> u64 {
+    let mut hasher = StableHasher::new();
+    env!("CARGO_PKG_VERSION").hash(&mut hasher);
+    for heuristic in heuristics {
+        heuristic.info().hash(&mut hasher);
+    }
+    dangerous.hash(&mut hasher);
+    stale_artifact_days.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Loads all matches saved in the scan index at `path`, together with their stats (if calculated).
+///
+/// Unlike incremental scans, this works even if the index was built with different heuristics or settings.
+/// Returns [`None`] if the index does not exist or is invalid.
+pub fn indexed_matches(path: &Path) -> Option<Vec<MatchSummary>> {
+    let index: ScanIndex = load_json(path)?;
+    let matches = index.dirs.values().flat_map(|dir| &dir.matches).map(|indexed| MatchSummary {
+        path: indexed.path.clone(),
+        languages: indexed.languages.iter().map(|(name, _)| name.clone()).collect(),
+        stats: index.stats.get(&indexed.path).map(IndexedStats::restore),
+    });
+    Some(matches.collect())
+}
+
+/// Returns the modification time of a directory without following symlinks.
+pub(super) fn mtime(path: &Path) -> Option<SystemTime> {
+    fs::symlink_metadata(path).ok()?.modified().ok()
+}
+
+/// Returns the modification time of a file read by the heuristics, following symlinks.
+pub(crate) fn file_mtime(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).ok()?.modified().ok()
+}
+
+/// Converts the path to the form saved in the index, see [`ScanIndex`].
+fn index_key(path: &Path) -> PathBuf {
+    PathBuf::from(path.to_string_lossy().into_owned())
+}
+
+/// Scan index shared between the scanner and the dir stats pool during a single scan.
+///
+/// Results of the previous scan are reused for directories which have not been modified since then,
+/// while the current results are collected into a new index. It is saved when the last reference to this
+/// struct is dropped, i.e. after both the scan and stats calculation have finished,
+/// but only if the scan has been completed (see [`Self::complete()`]).
+pub(super) struct IndexUpdate {
+    path: PathBuf,
+    heuristics: Vec<&'static dyn Heuristic>,
+    previous: ScanIndex,
+    /// Directories of the previous index whose subtrees can be reused, see [`ScanIndex::unchanged_subtrees()`].
+    unchanged: HashSet<PathBuf>,
+    current: Mutex<ScanIndex>,
+    completed: AtomicBool,
+    aborted: AtomicBool,
+}
+
+impl fmt::Debug for IndexUpdate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IndexUpdate").field("path", &self.path).finish_non_exhaustive()
+    }
+}
+
+impl IndexUpdate {
+    /// Loads the index from `path`, discarding it if it was built with different heuristics or settings,
+    /// i.e. if its [`fingerprint()`] differs.
+    pub(super) fn load(path: PathBuf, heuristics: Vec<&'static dyn Heuristic>, fingerprint: u64) -> Self {
+        let previous = match load_json::<ScanIndex>(&path) {
+            Some(index) if index.fingerprint == fingerprint => {
+                info!("Loaded scan index with {} directories from {:?}", index.dirs.len(), path);
+                index
+            },
+            Some(_) => {
+                info!("Heuristics or scanner settings have changed, discarding scan index {:?}", path);
+                ScanIndex::default()
+            },
+            None => ScanIndex::default(),
+        };
+        Self {
+            path,
+            heuristics,
+            unchanged: previous.unchanged_subtrees(),
+            previous,
+            current: Mutex::new(ScanIndex {
+                fingerprint,
+                ..ScanIndex::default()
+            }),
+            completed: AtomicBool::new(false),
+            aborted: AtomicBool::new(false),
+        }
+    }
+
+    /// Marks the scan as completed, allowing the new index to be saved.
+    pub(super) fn complete(&self) {
+        self.completed.store(true, Ordering::Relaxed);
+    }
+
+    /// Marks the scan as aborted, so that the incomplete index is never saved.
+    pub(super) fn abort(&self) {
+        self.aborted.store(true, Ordering::Relaxed);
+    }
+
+    /// Saves a scanned directory with its matches to the new index.
+    ///
+    /// The modification time has to be read before running the heuristics,
+    /// so that changes made while the directory was being processed are detected by the next scan.
+    /// The same applies to the `files` read by the heuristics, see [`crate::heuristics::read_to_string()`].
+    pub(super) fn record_dir(
+        &self,
+        path: &Path,
+        mtime: Option<SystemTime>,
+        state: u64,
+        matches: &[MatchData],
+        files: Vec<(PathBuf, Option<SystemTime>)>,
+    ) {
+        // Matches with non UTF-8 paths cannot be restored, so such directory always needs to be scanned again.
+        // The same applies to matches outside of it, as they may change without modifying it.
+        let restorable = matches
+            .iter()
+            .all(|data| data.path.to_str().is_some() && data.group.to_str().is_some() && data.path.starts_with(path));
+        let Ok(mut current) = self.current.lock() else {
+            return;
+        };
+        // Matches deeper in the subtree are recorded under the directory containing them,
+        // so that they don't prevent reusing all the directories above it
+        for data in matches {
+            let parent = data.path.parent().filter(|parent| parent.starts_with(path)).unwrap_or(path);
+            current.dir_entry(parent).matches.push(IndexedMatch::new(data));
+        }
+        let dir = current.dir_entry(path);
+        dir.mtime = mtime.filter(|_| restorable);
+        dir.state = Some(state);
+        dir.files = files;
+    }
+
+    /// Checks whether the directory at `path` and all its subdirectories are unchanged since the previous scan
+    /// (see [`ScanIndex::unchanged_subtrees()`]), and that the directory inherits the same scanner state as before.
+    ///
+    /// If so, the subtree is copied to the new index and all its matches are returned, so it does not need to be
+    /// scanned again. Returns [`None`] otherwise.
+    pub(super) fn reuse_subtree(&self, path: &Path, state: u64) -> Option<Vec<MatchData>> {
+        let root = self.previous.dirs.get(path)?;
+        if root.state != Some(state) {
+            debug!("Inherited state changed for {:?}, scanning again", path);
+            return None;
+        }
+        if !self.unchanged.contains(path) {
+            debug!("Subtree of {:?} changed since the previous scan, scanning again", path);
+            return None;
+        }
+        let subtree: Vec<_> =
+            self.previous.dirs.range(path.to_owned()..).take_while(|(dir, _)| dir.starts_with(path)).collect();
+        // Matches found by directories outside of the subtree are found again by them, if they are still valid
+        let indexed_matches: Vec<_> = subtree
+            .iter()
+            .flat_map(|(_, dir)| &dir.matches)
+            .filter(|indexed| indexed.group.starts_with(path))
+            .collect();
+        let matches =
+            indexed_matches.into_iter().map(|indexed| indexed.restore(&self.heuristics)).collect::<Option<Vec<_>>>()?;
+
+        debug!(
+            "Reusing {} directories and {} matches from the scan index for {:?}",
+            subtree.len(),
+            matches.len(),
+            path
+        );
+        let mut current = self.current.lock().ok()?;
+        for (dir, indexed) in subtree {
+            let mut indexed = indexed.clone();
+            indexed.matches.retain(|indexed| indexed.group.starts_with(path));
+            // Keep matches recorded during this scan by the directories outside of the subtree
+            if let Some(recorded) = current.dirs.remove(dir) {
+                let outside = recorded.matches.into_iter().filter(|recorded| !recorded.group.starts_with(path));
+                indexed.matches.extend(outside);
+            }
+            current.dirs.insert(dir.clone(), indexed);
+        }
+        Some(matches)
+    }
+
+    /// Returns the size of the match at `path` found by the previous scan, even if it has been modified since then.
+    pub(super) fn previous_size(&self, path: &Path) -> Option<SizeEstimate> {
+        let indexed = self.previous.stats.get(path)?;
+        Some(SizeEstimate {
+            apparent_size: Size::from_bytes(indexed.apparent_size?),
+            disk_size: Size::from_bytes(indexed.disk_size?),
+        })
+    }
+
+    /// Returns [`DirStats`] of the match at `path`, reusing them from the previous scan
+    /// if no directory inside of the match has been modified since then.
+    ///
+    /// Reused stats are registered in `registry` without any hardlinks, as they are not saved in the index.
+    /// They are also calculated again if a `breakdown` is requested, but it has not been saved.
+    pub(super) fn dir_stats(
+        &self,
+        path: PathBuf,
+        registry: Option<&InodeRegistry>,
+        breakdown: Option<usize>,
+    ) -> DirStats {
+        // Calculated before the stats, so that concurrent modifications are not missed by the next scan
+        let tree_mtime = tree_mtime(&path);
+        let previous = self.previous.stats.get(&path).filter(|indexed| {
+            Some(indexed.tree_mtime) == tree_mtime && (breakdown.is_none() || indexed.breakdown.is_some())
+        });
+        let stats = if let Some(indexed) = previous {
+            debug!("Reusing stats from the scan index for {:?}", path);
+            let stats = indexed.restore();
+            if let Some(registry) = registry {
+                let sizes = ByteSizes {
+                    apparent: indexed.apparent_size.unwrap_or(0),
+                    disk: indexed.disk_size.unwrap_or(0),
+                };
+                registry.register(&path, sizes, HashMap::new());
+            }
+            stats
+        } else {
+            DirStats::calculate(path.clone(), registry, breakdown)
+        };
+
+        if let (Some(tree_mtime), Some(_), Ok(mut current)) = (tree_mtime, path.to_str(), self.current.lock()) {
+            current.stats.insert(path, IndexedStats::new(tree_mtime, &stats));
+        }
+        stats
+    }
+}
+
+impl Drop for IndexUpdate {
+    fn drop(&mut self) {
+        if !*self.completed.get_mut() || *self.aborted.get_mut() {
+            info!("Scan was not completed, scan index {:?} not updated", self.path);
+            return;
+        }
+        let Ok(current) = self.current.get_mut() else {
+            error!("Scan index poisoned, not saving {:?}", self.path);
+            return;
+        };
+
+        // Stats of matches which were not calculated in this scan (e.g. when stats were disabled) are still valid
+        let previous_stats = &self.previous.stats;
+        let kept: Vec<_> = current
+            .dirs
+            .values()
+            .flat_map(|dir| &dir.matches)
+            .filter(|indexed| !current.stats.contains_key(&indexed.path))
+            .filter_map(|indexed| Some((indexed.path.clone(), previous_stats.get(&indexed.path)?.clone())))
+            .collect();
+        current.stats.extend(kept);
+
+        match save_json(current, &self.path) {
+            Ok(()) => info!("Saved scan index with {} directories to {:?}", current.dirs.len(), self.path),
+            Err(err) => error!("Failed to save scan index to {:?}: {}", self.path, err),
+        }
+    }
+}
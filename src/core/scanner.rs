@@ -1,10 +1,21 @@
-use super::{Heuristic, InheritedFiles, MatchData, MatchingState};
+use super::{
+    checkpoint::CheckpointUpdate,
+    dir_stats::dir_stats_pool,
+    scan_index::{self, fingerprint, IndexUpdate},
+    size_estimate::size_estimate_pool,
+    DirStats, Heuristic, InheritedFiles, InodeRegistry, MatchData, MatchingState, SizeEstimate, StableHasher,
+};
 use jwalk::{ClientState, DirEntry, Result, WalkDirGeneric};
 use std::{
     collections::HashSet,
-    ffi::OsString,
+    ffi::{OsStr, OsString},
+    hash::{Hash, Hasher},
     path::{Path, PathBuf},
-    sync::mpsc::Sender,
+    sync::{
+        mpsc::{SendError, Sender},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
 };
 use tracing::{debug, error, info, trace};
 
@@ -15,10 +26,24 @@ pub(super) struct ScannerCache {
     pub(super) inherited_files: InheritedFiles,
     /// Channel to send matches to.
     pub(super) sender: Option<Sender<MatchData>>,
+    /// Queue of matched paths waiting for their [`DirStats`] to be calculated, if enabled.
+    pub(super) stats_queue: Option<Sender<PathBuf>>,
+    /// Queue of matched paths waiting for their [`SizeEstimate`], with the previous size if known, if enabled.
+    pub(super) estimate_queue: Option<Sender<(PathBuf, Option<SizeEstimate>)>>,
     /// True if any of the parent paths was marked as dangerous.
     pub(super) dangerous: bool,
     /// Trick to propagate dangerous flag properly using [`jwalk`]'s weird data structures...
     pub(super) marked_to_be_dangerous: HashSet<OsString>,
+    /// Index of the previous scan, if enabled.
+    pub(super) index: Option<Arc<IndexUpdate>>,
+    /// Time limit of the scan and the checkpoint collected after reaching it, if enabled.
+    pub(super) checkpoint: Option<Arc<CheckpointUpdate>>,
+    /// Root directory of the scan.
+    pub(super) root: Option<Arc<Path>>,
+    /// See [`Scanner::stale_artifact_days`].
+    pub(super) stale_artifact_days: u64,
+    /// Paths of all the matches sent during the scan, shared between all the directories.
+    pub(super) sent: Arc<Mutex<HashSet<PathBuf>>>,
 }
 
 impl ScannerCache {
@@ -31,6 +56,53 @@ impl ScannerCache {
         let sender = Some(sender);
         Self { sender, ..Default::default() }
     }
+
+    /// Updates the cache inherited from the parent directory when entering a directory with the given name.
+    fn enter(&mut self, name: Option<&OsStr>) {
+        if name.is_some_and(|name| self.marked_to_be_dangerous.contains(name)) {
+            self.dangerous = true;
+            self.marked_to_be_dangerous.clear();
+        }
+    }
+
+    /// Returns a hash of the state which affects heuristics results, used to validate the scan index.
+    fn state_hash(&self) -> u64 {
+        let mut hasher = StableHasher::new();
+        self.inherited_files.hash(&mut hasher);
+        self.dangerous.hash(&mut hasher);
+        let mut marked: Vec<_> = self.marked_to_be_dangerous.iter().collect();
+        marked.sort();
+        marked.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Sends a match and queues its [`DirStats`] calculation and [`SizeEstimate`], if enabled.
+    ///
+    /// Matches which have already been sent are skipped, as paths added by
+    /// [`MatchingState::add_match_path()`] may be found in many directories.
+    pub(super) fn send_match(&self, data: MatchData) -> std::result::Result<(), SendError<MatchData>> {
+        let path = data.path.clone();
+        if self.sent.lock().is_ok_and(|mut sent| !sent.insert(path.clone())) {
+            debug!("Match {:?} has already been sent, skipping", path);
+            return Ok(());
+        }
+        if let Some(checkpoint) = &self.checkpoint {
+            checkpoint.record_match(&data);
+        }
+        self.sender.as_ref().unwrap().send(data)?;
+        if let Some(queue) = &self.estimate_queue {
+            let previous = self.index.as_ref().and_then(|index| index.previous_size(&path));
+            if queue.send((path.clone(), previous)).is_err() {
+                error!("Size estimate thread is not running, skipping estimate");
+            }
+        }
+        if let Some(queue) = &self.stats_queue {
+            if queue.send(path).is_err() {
+                error!("Dir stats pool is not running, skipping stats");
+            }
+        }
+        Ok(())
+    }
 }
 
 impl ClientState for ScannerCache {
@@ -45,6 +117,9 @@ pub(super) type Entry = DirEntry<ScannerCache>;
 #[derive(Debug, Clone)]
 pub struct Scanner {
     /// Specifies where the scan should start.
+    ///
+    /// It is made absolute by [`Self::new()`], as heuristics resolve paths relative to the scanned directories
+    /// (e.g. `../target`), which would point to unrelated directories if the root was relative.
     pub root: PathBuf,
     /// List of heuristics to use while scanning.
     ///
@@ -57,13 +132,75 @@ pub struct Scanner {
     /// When false, excludes matches with negative weights from further search (mainly for hiding system files).
     /// When true, only matches with positive weights skip their subdirectories.
     pub dangerous: bool,
+    /// Enables calculating [`DirStats`] of every match in a background thread pool, as soon as it is found.
+    ///
+    /// Results are sent to this channel together with the matched path, possibly after the scan itself has ended.
+    /// The pool stops (and drops its senders) once every match found during the scan has been processed.
+    pub stats_sender: Option<Sender<(PathBuf, DirStats)>>,
+    /// Registry for hardlinks found while calculating stats in the background (see [`Self::stats_sender`]).
+    ///
+    /// Allows checking how much space is shared between matches, so that it is not counted multiple times.
+    pub inode_registry: Option<Arc<InodeRegistry>>,
+    /// Enables incremental scanning using an index stored in this file.
+    ///
+    /// Directories which have not been modified since the previous scan of the same root are not scanned again,
+    /// their matches are loaded from the index instead. The same applies to [`DirStats`] of unmodified matches.
+    /// The index is discarded if heuristics or scanner settings change, and updated after a completed scan.
+    ///
+    /// Only entries being added, removed or renamed change a directory's modification time. Project files read by
+    /// the heuristics (e.g. `Cargo.toml`) are checked on their own, but other files edited in place
+    /// (e.g. a `.gitignore`) may not be noticed until this option is disabled for a single scan.
+    pub index_path: Option<PathBuf>,
+    /// Enables quick [`SizeEstimate`]s of every match in a background thread, as soon as it is found.
+    ///
+    /// Results are sent to this channel together with the matched path, usually long before its [`DirStats`].
+    /// Sizes from [`Self::index_path`] are used as estimates of matches modified since the previous scan.
+    pub estimate_sender: Option<Sender<(PathBuf, SizeEstimate)>>,
+    /// Number of largest subdirectories and file extensions to retain in [`DirStats::breakdown`]
+    /// of every match, if calculated in the background (see [`Self::stats_sender`]).
+    pub breakdown: Option<usize>,
+    /// Stops the scan gracefully after this time.
+    ///
+    /// Directories which are being scanned when the time runs out are finished, but their subdirectories
+    /// are skipped. If [`Self::checkpoint_path`] is set, they are saved there to be scanned later.
+    /// The [`Self::index_path`] is not updated by stopped scans.
+    pub time_limit: Option<Duration>,
+    /// File for saving the directories left unscanned after reaching the [`Self::time_limit`],
+    /// together with the matches found so far.
+    ///
+    /// It is removed once a scan of the same root completes.
+    pub checkpoint_path: Option<PathBuf>,
+    /// Continues the scan stopped by its time limit from the checkpoint saved in [`Self::checkpoint_path`].
+    ///
+    /// Matches found before the checkpoint are sent first (unless they no longer exist), followed by the matches
+    /// found in the remaining directories. The whole root is scanned if the checkpoint does not exist,
+    /// or if it was saved with different heuristics or settings.
+    pub resume: bool,
+    /// Build artifacts not rebuilt for this many days are offered for cleaning by heuristics which support it,
+    /// e.g. units in Cargo target directories. Defaults to 30 days.
+    pub stale_artifact_days: u64,
 }
 
 impl Scanner {
     /// Constructs a new [`Scanner`] with a default heuristics list.
     #[inline]
     pub fn new(root_path: &Path, sender: Sender<MatchData>) -> Self {
-        Self { root: root_path.to_owned(), sender, heuristics: crate::ALL_HEURISTICS.to_vec(), dangerous: false }
+        let root = std::path::absolute(root_path).unwrap_or_else(|_| root_path.to_owned());
+        Self {
+            root: crate::heuristics::normalize(&root),
+            sender,
+            heuristics: crate::ALL_HEURISTICS.to_vec(),
+            dangerous: false,
+            stats_sender: None,
+            inode_registry: None,
+            estimate_sender: None,
+            index_path: None,
+            breakdown: None,
+            time_limit: None,
+            checkpoint_path: None,
+            resume: false,
+            stale_artifact_days: 30,
+        }
     }
 
     /// Starts a scan. This is a blocking operation.
@@ -81,40 +218,175 @@ impl Scanner {
     /// Some directories may be skipped if they are already matched by a parent directory.
     pub fn scan_with_progress(self) -> impl Iterator<Item = Result<PathBuf>> {
         info!("Starting scan: {:#?}", self);
-        WalkDirGeneric::<ScannerCache>::new(self.root)
-            .root_read_dir_state(ScannerCache::new(self.sender))
-            .skip_hidden(false)
-            .process_read_dir(move |_depth, path, read_dir_state, children| {
-                debug!("Scanning directory: {:#?}", path);
-
-                if path.file_name().is_some_and(|name| read_dir_state.marked_to_be_dangerous.contains(name)) {
-                    read_dir_state.dangerous = true;
-                    read_dir_state.marked_to_be_dangerous.clear();
-                }
+        let fingerprint = fingerprint(&self.heuristics, self.dangerous, self.stale_artifact_days);
+        let index = self.index_path.map(|path| Arc::new(IndexUpdate::load(path, self.heuristics.clone(), fingerprint)));
+        let checkpoint = (self.time_limit.is_some() || self.checkpoint_path.is_some()).then(|| {
+            let deadline = self.time_limit.map(|limit| Instant::now() + limit);
+            Arc::new(CheckpointUpdate::new(self.checkpoint_path, self.heuristics.clone(), fingerprint, deadline))
+        });
+        let mut root_cache = ScannerCache::new(self.sender);
+        root_cache.root = Some(Arc::from(self.root.as_path()));
+        root_cache.stale_artifact_days = self.stale_artifact_days;
+        root_cache.index = index.clone();
+        root_cache.checkpoint = checkpoint.clone();
+        root_cache.estimate_queue = self.estimate_sender.map(size_estimate_pool);
+        root_cache.stats_queue =
+            self.stats_sender.map(|tx| dir_stats_pool(tx, self.inode_registry, index.clone(), self.breakdown));
+
+        let resumed = checkpoint.as_ref().filter(|_| self.resume).and_then(|checkpoint| checkpoint.resume(&root_cache));
+        let (roots, resumed) = if let Some((matches, pending)) = resumed {
+            // The index would only contain the remaining directories
+            if let Some(index) = &index {
+                index.abort();
+            }
+            if matches.into_iter().try_for_each(|data| root_cache.send_match(data)).is_err() {
+                error!("Sender failure!");
+            }
+            (pending, true)
+        } else {
+            (vec![(self.root, root_cache)], false)
+        };
 
-                let mut filtered_children: Vec<&mut Entry> =
-                    children.iter_mut().map(Result::as_mut).filter_map(|v| v.ok()).collect();
-                let mut state = MatchingState::new(&mut filtered_children, read_dir_state, path);
-                for heuristic in &self.heuristics {
-                    state.current_heuristic = Some(*heuristic);
-                    trace!("Running heuristic {} for path {}", heuristic.info(), path.display());
-                    heuristic.check_for_matches(&mut state);
+        let heuristics = self.heuristics;
+        let dangerous = self.dangerous;
+        roots
+            .into_iter()
+            .flat_map(move |(root, cache)| walk(root, cache, heuristics.clone(), dangerous, resumed))
+            .chain(std::iter::from_fn(move || {
+                // Runs once, after every directory has been scanned
+                let stopped = checkpoint.as_ref().is_some_and(|checkpoint| checkpoint.stopped());
+                if let Some(checkpoint) = &checkpoint {
+                    checkpoint.finish();
+                }
+                if let (Some(index), false) = (&index, stopped) {
+                    index.complete();
                 }
+                None
+            }))
+    }
+}
+
+/// Scans a single directory tree, starting with the state of its parent in `cache`.
+///
+/// The parent itself is processed by [`jwalk`] as well, with the root as its only child.
+/// When `resumed`, it has already been scanned by the stopped scan, so its heuristics are not run again.
+fn walk(
+    root: PathBuf,
+    cache: ScannerCache,
+    heuristics: Vec<&'static dyn Heuristic>,
+    dangerous: bool,
+    resumed: bool,
+) -> impl Iterator<Item = Result<PathBuf>> {
+    WalkDirGeneric::<ScannerCache>::new(root)
+        .root_read_dir_state(cache)
+        .skip_hidden(false)
+        .process_read_dir(move |depth, path, read_dir_state, children| {
+            if resumed && depth.is_none() {
+                defer_expired_children(read_dir_state, children);
+                return;
+            }
+            debug!("Scanning directory: {:#?}", path);
 
-                if state.process_collected_data(self.dangerous).is_err() {
-                    // Stop iteration as fast as possible
-                    children.clear();
-                    error!("Sender failure!");
-                } else {
-                    // Skip files in the progress iteration, yield only directories and errors
-                    children.retain(|f| if let Ok(f) = f { f.file_type.is_dir() } else { true });
+            read_dir_state.enter(path.file_name());
+            // The root's parent is processed with the root as its only child, so it can't be indexed
+            let index = read_dir_state.index.clone().filter(|_| depth.is_some());
+            let state_hash = index.as_ref().map(|_| read_dir_state.state_hash());
+            let mtime = index.as_ref().and_then(|_| scan_index::mtime(path));
+
+            let mut filtered_children: Vec<&mut Entry> =
+                children.iter_mut().map(Result::as_mut).filter_map(|v| v.ok()).collect();
+            let mut state = MatchingState::new(&mut filtered_children, read_dir_state, path);
+            // Forget files read for directories processed before on this thread, which may not have been recorded
+            crate::heuristics::take_read_files();
+            for heuristic in &heuristics {
+                state.current_heuristic = Some(*heuristic);
+                trace!("Running heuristic {} for path {}", heuristic.info(), path.display());
+                heuristic.check_for_matches(&mut state);
+            }
+
+            let matches = state.process_collected_data(dangerous);
+            skip_matched_children(read_dir_state, children);
+            let reused = match (matches, &index, state_hash) {
+                (Err(_), ..) => Err(()),
+                (Ok(matches), Some(index), Some(state_hash)) => {
+                    index.record_dir(path, mtime, state_hash, &matches, crate::heuristics::take_read_files());
+                    reuse_indexed_children(index, read_dir_state, children)
+                },
+                (Ok(_), ..) => Ok(()),
+            };
+
+            if reused.is_err() {
+                // Stop iteration as fast as possible
+                children.clear();
+                if let Some(index) = &read_dir_state.index {
+                    index.abort();
                 }
-            })
-            .into_iter()
-            .map(|entry| {
-                let path = entry.map(|e| e.path());
-                trace!("Scan progress: {path:#?}");
-                path
-            })
+                if let Some(checkpoint) = &read_dir_state.checkpoint {
+                    checkpoint.abort();
+                }
+                error!("Sender failure!");
+            } else {
+                // Skip files in the progress iteration, yield only directories and errors
+                children.retain(|f| if let Ok(f) = f { f.file_type.is_dir() } else { true });
+                defer_expired_children(read_dir_state, children);
+            }
+        })
+        .into_iter()
+        .map(|entry| {
+            let path = entry.map(|e| e.path());
+            trace!("Scan progress: {path:#?}");
+            path
+        })
+}
+
+/// Skips scanning subdirectories which have already been matched by path, in this directory or any other.
+///
+/// Path matches may be added for directories deeper in the tree than the one in which they are found,
+/// and other heuristics would then match their contents again, like when scanning any other match.
+fn skip_matched_children(cache: &ScannerCache, children: &mut [Result<Entry>]) {
+    let Ok(sent) = cache.sent.lock() else {
+        return;
+    };
+    for child in children.iter_mut().filter_map(|child| child.as_mut().ok()) {
+        if child.file_type.is_dir() && child.read_children_path.is_some() && sent.contains(&child.path()) {
+            debug!("Skipping {:?}, which has already been matched", child.path());
+            child.read_children_path = None;
+        }
+    }
+}
+
+/// Skips scanning subdirectories once the time limit is reached, saving them to the checkpoint instead.
+fn defer_expired_children(cache: &ScannerCache, children: &mut [Result<Entry>]) {
+    let Some(checkpoint) = cache.checkpoint.as_ref().filter(|checkpoint| checkpoint.expired()) else {
+        return;
+    };
+    for child in children.iter_mut().filter_map(|child| child.as_mut().ok()) {
+        if child.file_type.is_dir() && child.read_children_path.is_some() {
+            child.read_children_path = None;
+            checkpoint.defer(child.path(), cache);
+        }
+    }
+}
+
+/// Skips scanning subdirectories which have not changed since the previous scan,
+/// sending their matches from the index instead.
+fn reuse_indexed_children(
+    index: &IndexUpdate,
+    cache: &ScannerCache,
+    children: &mut [Result<Entry>],
+) -> std::result::Result<(), ()> {
+    for child in children.iter_mut().filter_map(|child| child.as_mut().ok()) {
+        if !child.file_type.is_dir() || child.read_children_path.is_none() {
+            continue;
+        }
+        let mut child_cache = cache.clone();
+        child_cache.enter(Some(&child.file_name));
+        if let Some(matches) = index.reuse_subtree(&child.path(), child_cache.state_hash()) {
+            child.read_children_path = None;
+            for data in matches {
+                cache.send_match(data).map_err(|_| ())?;
+            }
+        }
     }
+    Ok(())
 }
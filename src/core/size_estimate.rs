@@ -0,0 +1,134 @@
+use super::{dir_stats::allocated_size, inode_registry::ByteSizes, SizeKind};
+use size::Size;
+use std::{
+    fs,
+    iter::Sum,
+    ops::Add,
+    path::{Path, PathBuf},
+    sync::mpsc::{channel, Sender},
+    thread,
+};
+use tracing::{debug, error, info, trace};
+
+/// Maximum number of entries read while sampling a single match.
+const SAMPLED_ENTRIES: usize = 2000;
+/// Maximum depth of subdirectories read while sampling a match.
+const SAMPLED_DEPTH: usize = 3;
+
+/// Rough size of a match, available long before its [`DirStats`](super::DirStats) are calculated.
+///
+/// It is either the size from the previous scan, or sampled from a limited number of entries.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SizeEstimate {
+    pub apparent_size: Size,
+    pub disk_size: Size,
+}
+
+impl Add for SizeEstimate {
+    type Output = SizeEstimate;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        SizeEstimate {
+            apparent_size: self.apparent_size + rhs.apparent_size,
+            disk_size: self.disk_size + rhs.disk_size,
+        }
+    }
+}
+
+impl Sum for SizeEstimate {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(SizeEstimate::default(), |prev, current| prev + current)
+    }
+}
+
+impl SizeEstimate {
+    /// Estimates the size of a directory by reading at most a few thousand entries in its top levels.
+    ///
+    /// Subdirectories which are left unread are assumed to be as big as the average of their read siblings.
+    pub fn sample(path: &Path) -> Self {
+        let mut budget = SAMPLED_ENTRIES;
+        let sizes = sample_dir(path, 0, &mut budget);
+        debug!("Estimated size of {:?}: {:?}", path, sizes);
+        Self::from(sizes)
+    }
+
+    /// Returns the size of the selected kind.
+    #[inline]
+    pub fn size(&self, kind: SizeKind) -> Size {
+        match kind {
+            SizeKind::Disk => self.disk_size,
+            SizeKind::Apparent => self.apparent_size,
+        }
+    }
+}
+
+impl From<ByteSizes> for SizeEstimate {
+    fn from(sizes: ByteSizes) -> Self {
+        Self {
+            apparent_size: Size::from_bytes(sizes.apparent),
+            disk_size: Size::from_bytes(sizes.disk),
+        }
+    }
+}
+
+fn sample_dir(path: &Path, depth: usize, budget: &mut usize) -> ByteSizes {
+    let mut total = ByteSizes::default();
+    let Ok(entries) = fs::read_dir(path) else {
+        return total;
+    };
+    let mut subdirs = vec![];
+    for entry in entries.flatten() {
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        *budget = budget.saturating_sub(1);
+        if metadata.is_symlink() {
+            continue;
+        }
+        total += ByteSizes {
+            apparent: metadata.len(),
+            disk: allocated_size(&metadata),
+        };
+        if metadata.is_dir() {
+            subdirs.push(entry.path());
+        }
+    }
+
+    let mut sampled = ByteSizes::default();
+    let mut sampled_count = 0;
+    for subdir in &subdirs {
+        if *budget == 0 || depth >= SAMPLED_DEPTH {
+            break;
+        }
+        sampled += sample_dir(subdir, depth + 1, budget);
+        sampled_count += 1;
+    }
+    total += sampled;
+    if sampled_count > 0 {
+        let unsampled = (subdirs.len() - sampled_count) as u64;
+        total += ByteSizes {
+            apparent: sampled.apparent / sampled_count as u64 * unsampled,
+            disk: sampled.disk / sampled_count as u64 * unsampled,
+        };
+    }
+    total
+}
+
+/// Starts a thread which sends a [`SizeEstimate`] for every matched path sent to the returned queue.
+///
+/// Paths are queued together with their previous size, if known, which is then sent instead of sampling them.
+pub(super) fn size_estimate_pool(tx: Sender<(PathBuf, SizeEstimate)>) -> Sender<(PathBuf, Option<SizeEstimate>)> {
+    info!("Starting size estimate thread.");
+    let (queue_tx, queue_rx) = channel::<(PathBuf, Option<SizeEstimate>)>();
+    thread::spawn(move || {
+        for (path, previous) in queue_rx {
+            let estimate = previous.unwrap_or_else(|| SizeEstimate::sample(&path));
+            if tx.send((path, estimate)).is_err() {
+                error!("Failed to send");
+                break;
+            }
+        }
+        trace!("Size estimate queue closed, stopping thread.");
+    });
+    queue_tx
+}
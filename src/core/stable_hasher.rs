@@ -0,0 +1,35 @@
+use std::hash::Hasher;
+
+/// 64-bit FNV-1a hasher, used for hashes saved on disk.
+///
+/// Unlike [`DefaultHasher`](std::hash::DefaultHasher), its output does not change between Rust releases,
+/// so saved files are not invalidated (and index files are not renamed) after a toolchain upgrade.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct StableHasher(u64);
+
+impl StableHasher {
+    const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const PRIME: u64 = 0x100000001b3;
+
+    pub(crate) fn new() -> Self {
+        Self(Self::OFFSET_BASIS)
+    }
+}
+
+impl Default for StableHasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Hasher for StableHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 = (self.0 ^ u64::from(*byte)).wrapping_mul(Self::PRIME);
+        }
+    }
+}
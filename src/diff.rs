@@ -0,0 +1,82 @@
+use crate::{
+    core::{indexed_matches, DirStats, MatchData, MatchSummary, ScanDiff, SizeKind},
+    log::get_index_path,
+    Scanner, GLOBAL_CACHE_HEURISTICS,
+};
+use size::Size;
+use std::{collections::HashMap, env::current_dir, path::PathBuf};
+
+/// Maximum number of grown matches listed for each language.
+const MAX_GROWN: usize = 10;
+
+fn format_size(size: Option<Size>) -> String {
+    size.map_or("---".to_owned(), |size| size.to_string())
+}
+
+pub fn run(args: super::args::Args) {
+    let directory = args.path.unwrap_or_else(|| {
+        current_dir().expect("Cannot access current directory, run with -p <DIR> to select another")
+    });
+    let kind = if args.apparent_size { SizeKind::Apparent } else { SizeKind::Disk };
+    // Loaded before scanning, as the scan replaces the index with its own results
+    let index_path = get_index_path(&directory);
+    let previous = indexed_matches(&index_path);
+
+    println!("Scanning {}...", directory.display());
+    let (sender, receiver) = std::sync::mpsc::channel::<MatchData>();
+    let (stats_sender, stats_receiver) = std::sync::mpsc::channel::<(PathBuf, DirStats)>();
+    let mut scanner = Scanner::new(&directory, sender);
+    scanner.dangerous = args.dangerous;
+    if let Some(days) = args.stale_artifacts {
+        scanner.stale_artifact_days = days;
+    }
+    if args.global_caches {
+        scanner.heuristics.extend(GLOBAL_CACHE_HEURISTICS);
+    }
+    scanner.stats_sender = Some(stats_sender);
+    scanner.index_path = Some(index_path);
+    scanner.scan_with_progress().for_each(|progress| {
+        if let Err(error) = progress {
+            if let Some(path) = error.path() {
+                println!("Failed to read {} ({})", path.display(), error);
+            }
+        }
+    });
+    let matches: Vec<_> = receiver.iter().collect();
+    let mut stats: HashMap<_, _> = stats_receiver.iter().collect();
+    let current: Vec<_> = matches.iter().map(|data| MatchSummary::new(data, stats.remove(&data.path))).collect();
+
+    let Some(previous) = previous else {
+        println!("No previous scan of this directory found, {} matches saved for the next comparison.", current.len());
+        return;
+    };
+    let diff = ScanDiff::new(&previous, &current, kind);
+    if diff.is_empty() {
+        println!("No changes since the previous scan.");
+        return;
+    }
+
+    for (lang, changes) in &diff.languages {
+        println!("{}:", lang);
+        for summary in &changes.new {
+            println!("\t+ {} ({})", summary.path.display(), format_size(summary.size(kind)));
+        }
+        for summary in &changes.removed {
+            println!("\t- {} ({})", summary.path.display(), format_size(summary.size(kind)));
+        }
+        for growth in changes.grown.iter().take(MAX_GROWN) {
+            let percent = growth.percent().map_or(String::new(), |percent| format!(", +{:.1}%", percent));
+            println!(
+                "\t^ {} ({} -> {}, +{}{})",
+                growth.path.display(),
+                growth.before,
+                growth.after,
+                Size::from_bytes(growth.bytes()),
+                percent
+            );
+        }
+        if changes.grown.len() > MAX_GROWN {
+            println!("\t  ...and {} more grown", changes.grown.len() - MAX_GROWN);
+        }
+    }
+}
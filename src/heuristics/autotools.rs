@@ -0,0 +1,16 @@
+use crate::heuristic;
+
+heuristic!(Autotools, "", "autotools", IconColor(245), state, {
+    if state.has_file("configure").is_none() {
+        return;
+    }
+    if state.has_directory("autom4te.cache").is_some() {
+        state.add_match("autom4te.cache", "Found configure script and autom4te.cache directory.");
+    }
+    // Results of an in-tree configure run, which are recreated by running it again
+    for file in ["config.status", "config.log"] {
+        if state.has_file(file).is_some() {
+            state.add_match(file, &format!("Found configure script and {file} of an in-tree build.")).weight(500);
+        }
+    }
+});
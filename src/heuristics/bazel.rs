@@ -0,0 +1,42 @@
+use crate::heuristic;
+use std::fs;
+
+/// Prefix of the convenience symlinks created by Bazel in the workspace, all pointing into its output base.
+const SYMLINK_PREFIX: &str = "bazel-";
+
+/// Symlink to the output directory. `bazel-bin` and `bazel-testlogs` point into it,
+/// so only the directory it points to has to be matched.
+const OUTPUT_SYMLINK: &str = "bazel-out";
+
+heuristic!(Bazel, "", "bazel", IconColor(34), state, {
+    let workspace =
+        ["MODULE.bazel", "WORKSPACE", "WORKSPACE.bazel"].into_iter().any(|file| state.has_file(file).is_some());
+    if !workspace {
+        return;
+    }
+    // Symlinks are never followed by the scanner, so the directory they point to is matched instead,
+    // which usually lies outside of the scanned directory (in the output base, e.g. `~/.cache/bazel`)
+    let link = state.path().join(OUTPUT_SYMLINK);
+    if link.is_symlink() {
+        if let Ok(output) = fs::canonicalize(&link) {
+            let comment = if state.is_outside_root(&output) {
+                "Found Bazel workspace and output directory linked from bazel-out, outside of the scanned directory."
+            } else {
+                "Found Bazel workspace and output directory linked from bazel-out."
+            };
+            state.add_match_path(&output, comment);
+        }
+    }
+    // The symlinks would be left dangling once the output directory is removed
+    let symlinks: Vec<_> = state
+        .get_all_contents()
+        .filter(|(path, file_type)| {
+            file_type.is_symlink()
+                && path.file_name().is_some_and(|name| name.to_string_lossy().starts_with(SYMLINK_PREFIX))
+        })
+        .filter_map(|(path, _)| path.file_name().map(ToOwned::to_owned))
+        .collect();
+    for name in symlinks {
+        state.add_match(&name, "Found Bazel workspace and convenience symlink into its output base.");
+    }
+});
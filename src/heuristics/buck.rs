@@ -0,0 +1,7 @@
+use crate::heuristic;
+
+heuristic!(Buck, "", "buck", IconColor(33), state, {
+    if state.has_file(".buckconfig").is_some() && state.has_directory("buck-out").is_some() {
+        state.add_match("buck-out", "Found .buckconfig and buck-out directory.");
+    }
+});
@@ -0,0 +1,35 @@
+use super::{read_to_string, add_match_inside, normalize};
+use crate::heuristic;
+use std::path::Path;
+
+/// Returns the install path configured locally in `.bundle/config` of the project in `dir`.
+fn configured_path(dir: &Path) -> Option<String> {
+    let config = read_to_string(dir.join(".bundle/config")).ok()?;
+    let path = config.lines().find_map(|line| line.strip_prefix("BUNDLE_PATH:"))?;
+    Some(path.trim().trim_matches(['"', '\'']).to_owned())
+}
+
+heuristic!(Bundler, "", "ruby", IconColor(160), state, {
+    if state.has_file("Gemfile.lock").is_none() {
+        return;
+    }
+    let dir = state.path().to_owned();
+    // Gems are reinstalled with `bundle install` to the path configured in .bundle, `vendor/bundle` by convention
+    let mut paths = vec![dir.join("vendor/bundle")];
+    if let Some(path) = configured_path(&dir).filter(|path| !path.is_empty() && !path.starts_with('~')) {
+        let path = normalize(&dir.join(path));
+        if !paths.contains(&path) {
+            paths.push(path);
+        }
+    }
+    // Local settings in `.bundle/config` are not reinstallable, so paths containing them are never matched
+    let config = dir.join(".bundle/config");
+    for path in paths {
+        if path.is_dir() && path.starts_with(&dir) && !config.starts_with(&path) {
+            add_match_inside(state, &path, "Found Gemfile.lock and installed gems.");
+        }
+    }
+    if state.has_directory(".bundle").is_some() && !config.exists() {
+        state.add_match(".bundle", "Found Gemfile.lock and .bundle directory without settings.").weight(2000);
+    }
+});
@@ -0,0 +1,10 @@
+use super::add_match_inside;
+use crate::heuristic;
+
+heuristic!(Carthage, "󰏗", "carthage", IconColor(208), state, {
+    // Only built frameworks are matched, checkouts may be used as git submodules
+    let build = state.path().join("Carthage/Build");
+    if state.has_file("Cartfile.resolved").is_some() && build.is_dir() {
+        add_match_inside(state, &build, "Found Cartfile.resolved and built frameworks.");
+    }
+});
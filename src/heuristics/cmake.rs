@@ -1,10 +1,153 @@
+use super::{normalize, read_to_string};
 use crate::heuristic;
-use regex::Regex;
+use serde::Deserialize;
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Minimal representation of `CMakePresets.json` and `CMakeUserPresets.json`.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Presets {
+    #[serde(default)]
+    configure_presets: Vec<ConfigurePreset>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ConfigurePreset {
+    name: String,
+    binary_dir: Option<String>,
+    #[serde(default)]
+    inherits: Inherits,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(untagged)]
+enum Inherits {
+    #[default]
+    None,
+    One(String),
+    Many(Vec<String>),
+}
+
+impl Inherits {
+    fn names(&self) -> &[String] {
+        match self {
+            Inherits::None => &[],
+            Inherits::One(name) => std::slice::from_ref(name),
+            Inherits::Many(names) => names,
+        }
+    }
+}
+
+/// Returns the source directory recorded in `CMakeCache.txt` of the build tree in `build_dir`.
+fn source_dir(build_dir: &Path) -> Option<PathBuf> {
+    let cache = read_to_string(build_dir.join("CMakeCache.txt")).ok()?;
+    cache.lines().find_map(|line| line.strip_prefix("CMAKE_HOME_DIRECTORY:INTERNAL=")).map(PathBuf::from)
+}
+
+/// Returns true if both paths point to the same existing directory.
+fn same_dir(a: &Path, b: &Path) -> bool {
+    matches!((fs::canonicalize(a), fs::canonicalize(b)), (Ok(a), Ok(b)) if a == b)
+}
+
+/// Returns build directories of all the configure presets in `presets` of the project in `source`.
+///
+/// `binaryDir` may be inherited from other presets in the same file. Only the `sourceDir`, `sourceParentDir`,
+/// `sourceDirName` and `presetName` macros are expanded, directories using any other macro are skipped.
+fn preset_binary_dirs(presets: &Path, source: &Path) -> Vec<PathBuf> {
+    let Some(presets) = read_to_string(presets).ok().and_then(|s| serde_json::from_str::<Presets>(&s).ok()) else {
+        return vec![];
+    };
+    let by_name: HashMap<_, _> = presets.configure_presets.iter().map(|preset| (&preset.name, preset)).collect();
+    let binary_dir = |preset: &ConfigurePreset| {
+        // Breadth-first search through the inherited presets, limited in case of cycles
+        let mut queue = vec![preset];
+        for _ in 0..by_name.len() + 1 {
+            let current = queue.first()?;
+            if current.binary_dir.is_some() {
+                return current.binary_dir.clone();
+            }
+            let inherited = current.inherits.names().iter().filter_map(|name| by_name.get(name).copied());
+            queue = queue[1..].iter().copied().chain(inherited).collect();
+        }
+        None
+    };
+
+    let source_name = source.file_name().unwrap_or_default().to_string_lossy();
+    let source_parent = source.parent().unwrap_or(source).to_string_lossy();
+    presets
+        .configure_presets
+        .iter()
+        .filter_map(|preset| {
+            let dir = binary_dir(preset)?
+                .replace("${sourceDir}", &source.to_string_lossy())
+                .replace("${sourceParentDir}", &source_parent)
+                .replace("${sourceDirName}", &source_name)
+                .replace("${presetName}", &preset.name);
+            (!dir.contains("${") && !dir.contains("$env{")).then(|| normalize(&source.join(dir)))
+        })
+        .collect()
+}
 
 heuristic!(CMake, "", "cmake", IconColor(34), state, {
-    for path in state.match_directory(&Regex::new(r"build").expect("Regex error :)")) {
-        if path.join("CMakeCache.txt").is_file() {
-            state.add_match(path.file_name().expect("Expected path"), "Found cmake build directory.");
+    let dir = state.path().to_owned();
+
+    // Build trees may have any name, but they always contain a cache which points to their sources,
+    // unless it's an in-source build, where only the generated files can be matched
+    let dirs: Vec<_> =
+        state.get_all_contents().filter(|(_, file_type)| file_type.is_dir()).map(|(path, _)| path).collect();
+    for path in dirs {
+        let Some(source) = source_dir(&path) else {
+            continue;
+        };
+        if same_dir(&source, &path) {
+            continue;
+        }
+        let name = path.file_name().expect("Expected path");
+        let weight = if name.to_string_lossy().starts_with('.') { 2000 } else { 1000 };
+        let params = state.add_match(name, "Found CMakeCache.txt in build directory.").weight(weight);
+        if !same_dir(&source, &dir) {
+            params.custom_group(source);
+        }
+    }
+    if state.has_directory("CMakeFiles").is_some() && source_dir(&dir).is_some_and(|source| same_dir(&source, &dir)) {
+        state.add_match("CMakeFiles", "Found CMakeCache.txt of an in-source build and CMakeFiles directory.");
+    }
+
+    if state.has_file("CMakeLists.txt").is_none() {
+        return;
+    }
+    // Presets usually put build trees deeper in the project or next to it
+    for presets in ["CMakePresets.json", "CMakeUserPresets.json"] {
+        let Some(presets) = state.has_file(presets) else {
+            continue;
+        };
+        for binary_dir in preset_binary_dirs(&presets, &dir) {
+            if binary_dir.join("CMakeCache.txt").is_file() && !same_dir(&binary_dir, &dir) {
+                state
+                    .add_match_path(&binary_dir, "Found CMake preset and its build directory.")
+                    .custom_group(dir.clone());
+            }
         }
     }
+    // Compilation databases are often linked from a build tree to the sources for the editors
+    let database = dir.join("compile_commands.json");
+    if database.is_symlink() {
+        let build_dir = fs::canonicalize(&database).ok().and_then(|path| path.parent().map(Path::to_path_buf));
+        if let Some(build_dir) = build_dir.filter(|path| path.join("CMakeCache.txt").is_file() && !same_dir(path, &dir))
+        {
+            state
+                .add_match_path(&build_dir, "Found compile_commands.json linked from build directory.")
+                .custom_group(dir.clone());
+        }
+    }
+    // Index built by clangd from the compilation database
+    let clangd = dir.join(".cache/clangd");
+    if clangd.is_dir() {
+        state.add_match_path(&clangd, "Found CMakeLists.txt and clangd index.");
+    }
 });
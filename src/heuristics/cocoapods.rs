@@ -0,0 +1,8 @@
+use crate::heuristic;
+
+heuristic!(CocoaPods, "󰏖", "pods", IconColor(196), state, {
+    // Manifest.lock is a copy of Podfile.lock made by every `pod install`
+    if state.has_file("Podfile.lock").is_some() && state.path().join("Pods/Manifest.lock").is_file() {
+        state.add_match("Pods", "Found Podfile.lock and installed pods.");
+    }
+});
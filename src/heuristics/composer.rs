@@ -0,0 +1,28 @@
+use super::{read_to_string, add_match_inside, normalize};
+use crate::heuristic;
+use std::path::Path;
+
+/// Returns the vendor directory configured in `composer.json`, `vendor` by default.
+fn vendor_dir(composer_json: &Path) -> String {
+    read_to_string(composer_json)
+        .ok()
+        .and_then(|json| serde_json::from_str::<serde_json::Value>(&json).ok())
+        .and_then(|json| json["config"]["vendor-dir"].as_str().map(str::to_owned))
+        .unwrap_or_else(|| "vendor".to_owned())
+}
+
+heuristic!(Composer, "", "php", IconColor(61), state, {
+    let (Some(composer_json), Some(_)) = (state.has_file("composer.json"), state.has_file("composer.lock")) else {
+        return;
+    };
+    let dir = state.path().to_owned();
+    let vendor = normalize(&dir.join(vendor_dir(&composer_json)));
+    // Hand-maintained vendor directories are common in PHP, so only the ones installed by Composer are matched
+    if vendor.starts_with(&dir)
+        && vendor != dir
+        && vendor.join("autoload.php").is_file()
+        && vendor.join("composer").is_dir()
+    {
+        add_match_inside(state, &vendor, "Found composer.json, composer.lock and installed packages.");
+    }
+});
@@ -0,0 +1,117 @@
+use super::{normalize, read_to_string};
+use crate::heuristic;
+use regex::Regex;
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::LazyLock,
+};
+
+/// Output directory properties of MSBuild with their default values.
+const OUTPUT_PROPERTIES: [(&str, &str); 2] = [("BaseOutputPath", "bin"), ("BaseIntermediateOutputPath", "obj")];
+
+/// XML comments, which may contain commented out properties.
+static COMMENT: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?s)<!--.*?-->").expect("Regex error :)"));
+
+/// Solutions and projects of all the .NET languages.
+static PROJECTS: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\.(csproj|fsproj|vbproj|sln)$").expect("Regex error :)"));
+
+/// Returns true if the output directory of the given property contains files created by MSBuild,
+/// i.e. outputs of the default configurations or files generated by NuGet restore.
+fn is_output_dir(property: &str, dir: &Path) -> bool {
+    match property {
+        "BaseIntermediateOutputPath" => {
+            dir.join("project.assets.json").is_file()
+                || fs::read_dir(dir)
+                    .into_iter()
+                    .flatten()
+                    .flatten()
+                    .any(|entry| entry.file_name().to_string_lossy().ends_with(".nuget.g.props"))
+        },
+        _ => ["Debug", "Release"].iter().any(|configuration| dir.join(configuration).is_dir()),
+    }
+}
+
+/// Returns the value of the first definition of an MSBuild property in the given file,
+/// with all the known properties (directories and the project name) expanded.
+///
+/// Returns [`None`] if the property is not defined or uses any other properties.
+fn read_property(file: &Path, property: &str, project: &Path) -> Option<String> {
+    let xml = read_to_string(file).ok()?;
+    let xml = COMMENT.replace_all(&xml, "");
+    let re = Regex::new(&format!(r"<{property}(?:\s[^>]*)?>([^<]*)</{property}>")).expect("Regex error :)");
+    let value = re.captures(&xml)?.get(1)?.as_str().trim().to_owned();
+
+    let with_slash = |path: &Path| format!("{}/", path.display());
+    let mut value = value
+        .replace("$(MSBuildThisFileDirectory)", &with_slash(file.parent()?))
+        .replace("$(MSBuildProjectDirectory)", &project.parent()?.display().to_string())
+        .replace("$(MSBuildProjectName)", &project.file_stem()?.to_string_lossy());
+    if value.contains("$(") {
+        return None;
+    }
+    if cfg!(not(windows)) {
+        value = value.replace('\\', "/");
+    }
+    Some(value)
+}
+
+/// Returns the output directories of a project with the sources of their configuration, if overridden.
+///
+/// Overrides are read from the project itself and the nearest `Directory.Build.props`, which MSBuild imports.
+/// They are only returned if their contents have been created by MSBuild, as they may point anywhere.
+fn output_dirs(project: &Path) -> Vec<(PathBuf, Option<String>)> {
+    let dir = project.parent().unwrap_or(project);
+    let props = dir.ancestors().map(|ancestor| ancestor.join("Directory.Build.props")).find(|props| props.is_file());
+    OUTPUT_PROPERTIES
+        .into_iter()
+        .filter_map(|(property, default)| {
+            let configured = [Some(project), props.as_deref()].into_iter().flatten().find_map(|file| {
+                let value = read_property(file, property, project)?;
+                Some((normalize(&dir.join(value)), Some(file.file_name()?.to_string_lossy().into_owned())))
+            });
+            match configured {
+                Some((output, _)) if !is_output_dir(property, &output) => None,
+                Some(configured) => Some(configured),
+                None => Some((dir.join(default), None)),
+            }
+        })
+        .collect()
+}
+
+heuristic!(DotNet, "󰪮", "net", IconColor(98), state, {
+    let files = state.match_file(&PROJECTS);
+    if files.is_empty() {
+        return;
+    }
+    let dir = state.path().to_owned();
+
+    // Projects in the same directory usually share their output directories, configured by the first one wins
+    let projects = files.iter().filter(|file| file.extension().is_some_and(|extension| extension != "sln"));
+    let outputs: BTreeMap<_, _> = projects.flat_map(|project| output_dirs(project)).rev().collect();
+    for (output, source) in outputs {
+        // Never match the project itself or any of its parents
+        if dir.starts_with(&output) {
+            continue;
+        }
+        let name = output.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+        let comment = match source {
+            Some(source) => format!("Found .NET project and output directory configured in {source}."),
+            None => format!("Found .NET project and {name} directory."),
+        };
+        if output.parent() != Some(&dir) {
+            state.add_match_path(&output, &comment);
+        } else if state.has_directory(&name).is_some() {
+            state.add_match(&name, &comment);
+        }
+    }
+
+    if state.has_directory(".vs").is_some() {
+        state.add_match(".vs", "Found .NET solution or project and .vs directory.").weight(2000);
+    }
+    if state.has_directory("TestResults").is_some() {
+        state.add_match("TestResults", "Found .NET solution or project and TestResults directory.");
+    }
+});
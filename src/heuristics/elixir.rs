@@ -0,0 +1,15 @@
+use crate::heuristic;
+
+heuristic!(Elixir, "", "ex", IconColor(135), state, {
+    if state.has_file("mix.exs").is_some() {
+        if state.has_directory("_build").is_some() {
+            state.add_match("_build", "Found mix.exs and _build directory.");
+        }
+        if state.has_directory("deps").is_some() {
+            state.add_match("deps", "Found mix.exs and deps directory.");
+        }
+        if state.has_directory(".elixir_ls").is_some() {
+            state.add_match(".elixir_ls", "Found mix.exs and .elixir_ls directory.").weight(2000);
+        }
+    }
+});
@@ -0,0 +1,7 @@
+use crate::heuristic;
+
+heuristic!(Erlang, "", "erl", IconColor(125), state, {
+    if state.has_file("rebar.config").is_some() && state.has_directory("_build").is_some() {
+        state.add_match("_build", "Found rebar.config and _build directory.");
+    }
+});
@@ -0,0 +1,44 @@
+use crate::heuristic;
+use std::{fs, io::Read, path::Path};
+
+/// Magic numbers of executable formats: ELF, PE and Mach-O (32 and 64-bit, both endiannesses and universal).
+const EXECUTABLE_MAGIC: [&[u8]; 7] = [
+    b"\x7fELF",
+    b"MZ",
+    b"\xfe\xed\xfa\xce",
+    b"\xfe\xed\xfa\xcf",
+    b"\xce\xfa\xed\xfe",
+    b"\xcf\xfa\xed\xfe",
+    b"\xca\xfe\xba\xbe",
+];
+
+/// Returns true if the file starts with a magic number of an executable format.
+fn is_executable(path: &Path) -> bool {
+    let mut magic = [0; 4];
+    let Ok(read) = fs::File::open(path).and_then(|mut file| file.read(&mut magic)) else {
+        return false;
+    };
+    EXECUTABLE_MAGIC.iter().any(|executable| magic[..read].starts_with(executable))
+}
+
+/// Returns true if the directory contains only compiled executables (e.g. from `go build -o bin/`),
+/// so that `bin` directories with scripts are left alone.
+fn has_only_executables(dir: &Path) -> bool {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return false;
+    };
+    let mut entries = entries.peekable();
+    entries.peek().is_some() && entries.all(|entry| entry.is_ok_and(|entry| is_executable(&entry.path())))
+}
+
+heuristic!(Go, "", "go", IconColor(74), state, {
+    if state.has_file("go.mod").is_none() {
+        return;
+    }
+    if state.has_directory("vendor").is_some_and(|vendor| vendor.join("modules.txt").is_file()) {
+        state.add_match("vendor", "Found go.mod and vendor directory.");
+    }
+    if state.has_directory("bin").is_some_and(|bin| has_only_executables(&bin)) {
+        state.add_match("bin", "Found go.mod and bin directory with executables.");
+    }
+});
@@ -0,0 +1,39 @@
+use crate::heuristic;
+use directories::BaseDirs;
+use std::{env, path::PathBuf};
+
+/// Returns the value of an environment variable, if set to a non-empty absolute path.
+fn env_path(name: &str) -> Option<PathBuf> {
+    env::var_os(name).map(PathBuf::from).filter(|path| path.is_absolute())
+}
+
+/// Returns the location of the Go build cache, see `go help cache`.
+fn build_cache() -> Option<PathBuf> {
+    if env::var_os("GOCACHE").is_some_and(|cache| cache == "off") {
+        return None;
+    }
+    // Go uses the same user cache directory, including the `XDG_CACHE_HOME` override on Linux
+    env_path("GOCACHE").or_else(|| Some(BaseDirs::new()?.cache_dir().join("go-build")))
+}
+
+/// Returns the location of the Go module cache, see `go help gopath`.
+fn module_cache() -> Option<PathBuf> {
+    let gopath = || env::split_paths(&env::var_os("GOPATH")?).next().filter(|path| path.is_absolute());
+    env_path("GOMODCACHE")
+        .or_else(|| Some(gopath().or_else(|| Some(BaseDirs::new()?.home_dir().join("go")))?.join("pkg/mod")))
+}
+
+heuristic!(GoCaches, "", "gocache", IconColor(74), state, {
+    if state.has_file("go.mod").is_none() {
+        return;
+    }
+    for (cache, comment) in [
+        (build_cache(), "Found go.mod and Go build cache used by it."),
+        (module_cache(), "Found go.mod and Go module cache used by it."),
+    ] {
+        // Caches are shared by all the projects, so they get groups of their own
+        if let Some(cache) = cache {
+            state.add_match_path(&cache, comment).custom_group(cache.clone());
+        }
+    }
+});
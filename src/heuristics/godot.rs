@@ -0,0 +1,12 @@
+use crate::heuristic;
+
+heuristic!(Godot, "", "godot", IconColor(67), state, {
+    if state.has_file("project.godot").is_some() {
+        // Godot 4 keeps imported assets and caches in .godot, while Godot 3 used .import
+        for dir in [".godot", ".import"] {
+            if state.has_directory(dir).is_some() {
+                state.add_match(dir, &format!("Found project.godot and {dir} directory.")).weight(2000);
+            }
+        }
+    }
+});
@@ -1,13 +1,101 @@
+use super::{normalize, read_to_string};
 use crate::heuristic;
 use regex::Regex;
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    sync::LazyLock,
+};
+
+/// Block and line comments of Groovy and Kotlin scripts.
+static COMMENT: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?s)/\*.*?\*/|//[^\n]*").expect("Regex error :)"));
+
+/// Calls of `include` with literal arguments.
+static INCLUDE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"\binclude\b\s*\(?((?:\s*["'][^"']+["']\s*,?)+)"#).expect("Regex error :)"));
+
+/// String literals.
+static STRING: LazyLock<Regex> = LazyLock::new(|| Regex::new(r#"["']([^"']+)["']"#).expect("Regex error :)"));
+
+/// Scripts of the Gradle wrapper.
+static WRAPPER: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^gradlew(\.bat)?$").expect("Regex error :)"));
+
+/// Settings scripts, which declare subprojects of the root project.
+static SETTINGS: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^settings\.gradle(\.kts)?$").expect("Regex error :)"));
+
+/// Build scripts of projects.
+static BUILD_FILE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^build\.gradle(\.kts)?$").expect("Regex error :)"));
+
+/// Assignments of literal `projectDir` overrides.
+static PROJECT_DIR: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r#"project\(\s*["'](:[^"']+)["']\s*\)\.projectDir\s*=\s*(?:file|new\s+File)\(\s*(?:[\w.]+\s*,\s*)?["']([^"']+)["']"#,
+    )
+    .expect("Regex error :)")
+});
+
+/// Returns directories of all the subprojects declared in a `settings.gradle(.kts)` file of the project in `root`.
+///
+/// Only literal `include` arguments and `projectDir` overrides are recognized, anything computed is skipped.
+fn subprojects(settings: &Path, root: &Path) -> Vec<PathBuf> {
+    let Ok(script) = read_to_string(settings) else {
+        return vec![];
+    };
+    let script = COMMENT.replace_all(&script, "");
+    let mut projects: BTreeMap<_, _> = INCLUDE
+        .captures_iter(&script)
+        .flat_map(|c| STRING.captures_iter(c.get(1).unwrap().as_str()).map(|c| c[1].to_owned()).collect::<Vec<_>>())
+        .map(|name| {
+            let path = root.join(name.trim_start_matches(':').replace(':', "/"));
+            (format!(":{}", name.trim_start_matches(':')), path)
+        })
+        .collect();
+
+    for c in PROJECT_DIR.captures_iter(&script) {
+        if let Some(path) = projects.get_mut(&c[1]) {
+            *path = normalize(&root.join(&c[2]));
+        }
+    }
+    projects.into_values().collect()
+}
 
 heuristic!(Gradle, "", "gradle", IconColor(25), state, {
-    if !state.match_file(&Regex::new(r"^gradlew(\.bat)?$").expect("Regex error :)")).is_empty() {
-        if state.has_directory("build").is_some() {
-            state.add_match("build", "Found gradlew and build directory.");
+    let dir = state.path().to_owned();
+    let wrapper = state.match_file(&WRAPPER);
+    let settings = state.match_file(&SETTINGS);
+    let build_file = state.match_file(&BUILD_FILE);
+
+    // Inherited files contain the root project followed by all the subprojects declared in its settings
+    let inherited = state.inherited_files();
+    let declared = inherited.iter().skip(1).any(|project| *project == dir);
+    let root = inherited.first().cloned();
+    let is_root = !declared && (!wrapper.is_empty() || !settings.is_empty());
+    if is_root {
+        inherited.clear();
+        inherited.push(dir.clone());
+    }
+    if let Some(settings) = settings.first() {
+        let projects = subprojects(settings, &dir);
+        state.inherited_files().extend(projects);
+    }
+
+    let (found, group) = if is_root {
+        let marker = wrapper.first().or(settings.first()).and_then(|path| path.file_name()).unwrap_or_default();
+        (marker.to_string_lossy().into_owned(), None)
+    } else if let Some(root) = root.filter(|_| declared || !build_file.is_empty()) {
+        ("a Gradle subproject".to_owned(), Some(root))
+    } else {
+        return;
+    };
+
+    for (name, weight) in [("build", 1000), (".cxx", 2000), (".gradle", 2000)] {
+        // Project caches are kept only in the root project
+        if state.has_directory(name).is_none() || (name == ".gradle" && group.is_some()) {
+            continue;
         }
-        if state.has_directory(".gradle").is_some() {
-            state.add_match(".gradle", "Found gradlew and .gradle directory.").weight(2000);
+        let params = state.add_match(name, &format!("Found {found} and {name} directory.")).weight(weight);
+        if let Some(group) = &group {
+            params.custom_group(group.clone());
         }
     }
 });
@@ -0,0 +1,17 @@
+use crate::heuristic;
+use regex::Regex;
+use std::sync::LazyLock;
+
+/// Cabal package descriptions and project files.
+static CABAL: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(\.cabal|^cabal\.project)$").expect("Regex error :)"));
+
+heuristic!(Haskell, "", "hs", IconColor(97), state, {
+    let cabal = state.match_file(&CABAL);
+    let stack = state.has_file("stack.yaml").is_some() || state.has_file("package.yaml").is_some();
+    if (stack || !cabal.is_empty()) && state.has_directory(".stack-work").is_some() {
+        state.add_match(".stack-work", "Found Stack project and .stack-work directory.").weight(2000);
+    }
+    if !cabal.is_empty() && state.has_directory("dist-newstyle").is_some() {
+        state.add_match("dist-newstyle", "Found Cabal project and dist-newstyle directory.");
+    }
+});
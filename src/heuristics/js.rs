@@ -1,7 +1,56 @@
 use crate::heuristic;
+use ignore::gitignore::Gitignore;
+use regex::Regex;
+use std::sync::LazyLock;
+
+/// Outputs and caches of frameworks and tools which are matched only if a file or directory matching
+/// the given regex (usually their config) exists next to them, with their weights and comments.
+///
+/// Hidden directories need higher weights to outweigh the `Hidden` heuristic.
+static TOOL_DIRS: LazyLock<[(&str, Regex, i32, &str); 11]> = LazyLock::new(|| {
+    [
+        (".next", r"^next\.config\.[cm]?[jt]s$", 2000, "Found Next.js config and .next build directory."),
+        (".nuxt", r"^nuxt\.config\.[cm]?[jt]s$", 2000, "Found Nuxt config and .nuxt build directory."),
+        (".output", r"^nuxt\.config\.[cm]?[jt]s$", 2000, "Found Nuxt config and .output directory."),
+        (".svelte-kit", r"^svelte\.config\.[cm]?[jt]s$", 2000, "Found Svelte config and .svelte-kit directory."),
+        (".angular/cache", r"^angular\.json$", 1000, "Found angular.json and Angular CLI cache."),
+        (".parcel-cache", r"^package\.json$", 2000, "Found package.json and Parcel cache."),
+        (".turbo", r"^turbo\.json$", 2000, "Found turbo.json and Turborepo cache."),
+        (".vite", r"^vite(st)?\.config\.[cm]?[jt]s$", 2000, "Found Vite config and .vite cache."),
+        ("storybook-static", r"^\.storybook$", 500, "Found .storybook config and static Storybook build."),
+        ("coverage", r"^(jest|vitest)\.config\.([cm]?[jt]s|json)$", 500, "Found Jest or Vitest config and coverage."),
+        (".pnpm-store", r"^pnpm-(lock|workspace)\.yaml$", 2000, "Found pnpm lockfile and pnpm store."),
+    ]
+    .map(|(dir, config, weight, comment)| (dir, Regex::new(config).expect("Regex error :)"), weight, comment))
+});
 
 heuristic!(JavaScript, "", "js", IconColor(221), state, {
-    if state.has_file("package.json").is_some() && state.has_directory("node_modules").is_some() {
+    if state.has_file("package.json").is_none() {
+        return;
+    }
+    if state.has_directory("node_modules").is_some() {
         state.add_match("node_modules", "Found node_modules and package file.");
     }
+
+    let names: Vec<_> = state.get_all_contents().filter_map(|(path, _)| Some(path.file_name()?.to_owned())).collect();
+    for (dir, config, weight, comment) in TOOL_DIRS.iter() {
+        if !names.iter().any(|name| name.to_str().is_some_and(|name| config.is_match(name))) {
+            continue;
+        }
+        let path = state.path().join(dir);
+        if dir.contains('/') && path.is_dir() {
+            state.add_match_path(&path, comment).weight(*weight);
+        } else if state.has_directory(dir).is_some() {
+            state.add_match(dir, comment).weight(*weight);
+        }
+    }
+
+    // With Zero-Installs, the Yarn cache is committed, so it is matched only if ignored, possibly with all of .yarn
+    let yarn_cache = state.path().join(".yarn/cache");
+    if let (Some(_), Some(gitignore)) = (state.has_file(".yarnrc.yml"), state.has_file(".gitignore")) {
+        let gitignore = Gitignore::new(gitignore).0;
+        if yarn_cache.is_dir() && gitignore.matched_path_or_any_parents(&yarn_cache, true).is_ignore() {
+            state.add_match_path(&yarn_cache, "Found .yarnrc.yml and ignored Yarn cache.");
+        }
+    }
 });
@@ -0,0 +1,13 @@
+use crate::heuristic;
+use regex::Regex;
+use std::sync::LazyLock;
+
+/// Gradle and Maven build files, next to which Kotlin creates its session cache.
+static BUILD_FILES: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^((build|settings)\.gradle(\.kts)?|pom\.xml)$").expect("Regex error :)"));
+
+heuristic!(Kotlin, "", "kt", IconColor(99), state, {
+    if state.has_directory(".kotlin").is_some() && !state.match_file(&BUILD_FILES).is_empty() {
+        state.add_match(".kotlin", "Found a Gradle or Maven build file and Kotlin session cache.").weight(2000);
+    }
+});
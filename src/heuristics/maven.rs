@@ -0,0 +1,161 @@
+use super::{normalize, read_to_string};
+use crate::heuristic;
+use regex::Regex;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::LazyLock,
+};
+
+/// Sections of `<build>` which may contain their own `<directory>` elements.
+const NESTED_BUILD_SECTIONS: [&str; 6] =
+    ["resources", "testResources", "plugins", "pluginManagement", "extensions", "filters"];
+
+/// Subdirectories created by Maven in every build directory, at least one of which has to exist
+/// before a configured build directory outside of the project is matched.
+const BUILD_DIRECTORY_CONTENTS: [&str; 3] = ["maven-status", "classes", "maven-archiver"];
+
+/// XML comments, which may contain commented out elements.
+static COMMENT: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?s)<!--.*?-->").expect("Regex error :)"));
+
+/// Definitions of `<properties>`.
+static PROPERTY: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"<([\w.\-]+)>([^<]*)</[\w.\-]+>").expect("Regex error :)"));
+
+/// References to properties, e.g. `${project.basedir}`.
+static INTERPOLATION: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\$\{([^}]+)\}").expect("Regex error :)"));
+
+/// Elements of `<modules>`.
+static MODULE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"<module>\s*([^<]*?)\s*</module>").expect("Regex error :)"));
+
+/// Scripts of the Maven wrapper.
+static WRAPPER: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^mvnw(\.cmd)?$").expect("Regex error :)"));
+
+/// Parts of a `pom.xml` file needed to find build directories.
+///
+/// Only the POM itself is read, so build directories inherited from parent POMs are not known.
+struct Pom {
+    /// Resolved `<build><directory>`, if configured.
+    build_directory: Option<PathBuf>,
+    /// Resolved paths of all `<modules>` of a multi-module project.
+    modules: Vec<PathBuf>,
+}
+
+/// Removes all the given elements with their contents.
+///
+/// Elements have to be removed (instead of being matched directly) because regexes can't handle nesting.
+fn strip_elements(xml: &str, elements: &[&str]) -> String {
+    elements.iter().fold(xml.to_owned(), |xml, element| {
+        let re = Regex::new(&format!(r"(?s)<{element}(\s[^>]*)?>.*?</{element}>")).expect("Regex error :)");
+        re.replace_all(&xml, "").into_owned()
+    })
+}
+
+/// Returns the trimmed text of the first `element`.
+fn element_text<'a>(xml: &'a str, element: &str) -> Option<&'a str> {
+    let re = Regex::new(&format!(r"(?s)<{element}(?:\s[^>]*)?>(.*?)</{element}>")).expect("Regex error :)");
+    Some(re.captures(xml)?.get(1)?.as_str().trim())
+}
+
+impl Pom {
+    fn read(pom: &Path, dir: &Path) -> Option<Self> {
+        let xml = read_to_string(pom).ok()?;
+        let xml = COMMENT.replace_all(&xml, "");
+        // Profiles are usually inactive, so their builds and modules are skipped
+        let xml = strip_elements(&xml, &["profiles", "dependencyManagement", "dependencies", "reporting"]);
+
+        let properties: HashMap<_, _> = element_text(&xml, "properties")
+            .map(|properties| {
+                PROPERTY.captures_iter(properties).map(|c| (c[1].to_owned(), c[2].trim().to_owned())).collect()
+            })
+            .unwrap_or_default();
+        let dir_string = dir.to_string_lossy();
+        let mut unresolved = false;
+        let mut interpolate = |value: &str| {
+            INTERPOLATION
+                .replace_all(value, |c: &regex::Captures| match &c[1] {
+                    "basedir" | "project.basedir" => dir_string.clone().into_owned(),
+                    property => properties.get(property).cloned().unwrap_or_else(|| {
+                        unresolved = true;
+                        String::new()
+                    }),
+                })
+                .into_owned()
+        };
+
+        let build_directory = element_text(&xml, "build")
+            .map(|build| strip_elements(build, &NESTED_BUILD_SECTIONS))
+            .and_then(|build| element_text(&build, "directory").map(&mut interpolate))
+            .filter(|directory| !directory.is_empty())
+            .map(|directory| normalize(&dir.join(directory)));
+        let build_directory = build_directory.filter(|_| !unresolved);
+
+        let modules = element_text(&xml, "modules")
+            .into_iter()
+            .flat_map(|modules| MODULE.captures_iter(modules))
+            .map(|c| {
+                let module = normalize(&dir.join(&c[1]));
+                // Modules may also point directly to their POM files
+                match module.extension() {
+                    Some(extension) if extension == "xml" => module.parent().map(Path::to_owned).unwrap_or(module),
+                    _ => module,
+                }
+            })
+            .collect();
+        Some(Self {
+            build_directory,
+            modules,
+        })
+    }
+}
+
+heuristic!(Maven, "", "mvn", IconColor(124), state, {
+    let Some(pom_path) = state.has_file("pom.xml") else {
+        return;
+    };
+    let dir = state.path().to_owned();
+    let pom = Pom::read(&pom_path, &dir);
+
+    // Inherited files contain the root of a multi-module project followed by all the modules found so far
+    let inherited = state.inherited_files();
+    let root = inherited.iter().skip(1).any(|module| *module == dir).then(|| inherited[0].clone());
+    if let Some(pom) = pom.as_ref().filter(|pom| !pom.modules.is_empty()) {
+        if root.is_none() {
+            inherited.clear();
+            inherited.push(dir.clone());
+        }
+        inherited.extend(pom.modules.iter().cloned());
+    }
+
+    if !state.match_file(&WRAPPER).is_empty() {
+        let wrapper = dir.join(".mvn/wrapper/maven-wrapper.jar");
+        if wrapper.is_file() {
+            state.add_match_path(&wrapper, "Found mvnw and Maven wrapper downloaded by it.");
+        }
+    }
+
+    let build_directory = pom.and_then(|pom| pom.build_directory).unwrap_or_else(|| dir.join("target"));
+    // Never match the project itself or any of its parents
+    if dir.starts_with(&build_directory) {
+        return;
+    }
+    let params = match build_directory.strip_prefix(&dir).ok().filter(|name| name.components().count() == 1) {
+        Some(name) if state.has_directory(&name.to_string_lossy()).is_some() => {
+            state.add_match(name, "Found pom.xml and target directory.")
+        },
+        Some(_) => return,
+        None if BUILD_DIRECTORY_CONTENTS.iter().any(|name| build_directory.join(name).is_dir()) => {
+            let comment = if state.is_outside_root(&build_directory) {
+                "Found pom.xml and build directory configured in it, outside of the scanned directory."
+            } else {
+                "Found pom.xml and build directory configured in it."
+            };
+            state.add_match_path(&build_directory, comment)
+        },
+        None => return,
+    };
+    if let Some(root) = root {
+        params.custom_group(root);
+    }
+});
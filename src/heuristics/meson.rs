@@ -0,0 +1,12 @@
+use crate::heuristic;
+
+heuristic!(Meson, "", "meson", IconColor(63), state, {
+    // Build directories may have any name, but they always contain Meson's private data
+    let dirs: Vec<_> =
+        state.get_all_contents().filter(|(_, file_type)| file_type.is_dir()).map(|(path, _)| path).collect();
+    for path in dirs {
+        if path.join("meson-private").is_dir() {
+            state.add_match(path.file_name().expect("Expected path"), "Found meson-private in build directory.");
+        }
+    }
+});
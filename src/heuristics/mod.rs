@@ -1,17 +1,45 @@
-use crate::core::Heuristic;
+use crate::core::{file_mtime, Heuristic, MatchParameters, MatchingState};
+use std::{
+    cell::RefCell,
+    fs, io,
+    path::{Component, Path, PathBuf},
+    time::SystemTime,
+};
 
+mod autotools;
+mod bazel;
+mod buck;
+mod bundler;
+mod carthage;
 mod cmake;
+mod cocoapods;
+mod composer;
 mod direnv;
+mod dotnet;
+mod elixir;
+mod erlang;
 mod flutter;
 mod git;
+mod go;
+mod go_caches;
+mod godot;
 mod gradle;
+mod haskell;
 mod hidden;
 mod js;
+mod kotlin;
+mod maven;
+mod meson;
+mod ocaml;
 mod python;
 mod rust;
+mod swift;
 mod system;
 mod unity;
+mod unreal;
 mod venv;
+mod xcode;
+mod zig;
 
 /// A list of all heuristics implemented by default in this crate.
 ///
@@ -22,6 +50,8 @@ pub const ALL_HEURISTICS: &[&dyn Heuristic] = &[
     &system::INSTANCE,
     &rust::INSTANCE,
     &unity::INSTANCE,
+    &unreal::INSTANCE,
+    &godot::INSTANCE,
     &js::INSTANCE,
     &python::INSTANCE,
     &git::INSTANCE,
@@ -30,4 +60,92 @@ pub const ALL_HEURISTICS: &[&dyn Heuristic] = &[
     &flutter::INSTANCE,
     &cmake::INSTANCE,
     &gradle::INSTANCE,
+    &maven::INSTANCE,
+    &kotlin::INSTANCE,
+    &dotnet::INSTANCE,
+    &go::INSTANCE,
+    &haskell::INSTANCE,
+    &ocaml::INSTANCE,
+    &erlang::INSTANCE,
+    &elixir::INSTANCE,
+    &zig::INSTANCE,
+    &swift::INSTANCE,
+    &meson::INSTANCE,
+    &autotools::INSTANCE,
+    &bazel::INSTANCE,
+    &buck::INSTANCE,
+    &bundler::INSTANCE,
+    &composer::INSTANCE,
+    &cocoapods::INSTANCE,
+    &carthage::INSTANCE,
+    &xcode::INSTANCE,
 ];
+
+/// Heuristics matching global caches shared by all the projects, which are not used by default.
+///
+/// They match caches used by projects found while scanning, but these usually lie outside of the scanned directory.
+pub const GLOBAL_CACHE_HEURISTICS: &[&dyn Heuristic] = &[&go_caches::INSTANCE];
+
+/// Removes `.` and `..` components from a path without accessing the filesystem, like build tools do.
+///
+/// Leading `..` components of relative paths are kept, as there is nothing to remove them with.
+pub(crate) fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {},
+            Component::ParentDir => match normalized.components().next_back() {
+                Some(Component::Normal(_)) => {
+                    normalized.pop();
+                },
+                Some(Component::RootDir | Component::Prefix(_)) => {},
+                _ => normalized.push(component),
+            },
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+/// Adds a match for a path inside of the current directory, as a regular match if it is a direct child,
+/// so that it is combined with matches of other heuristics and not scanned, or as a path match otherwise.
+///
+/// Hidden direct children get a higher weight to outweigh the `Hidden` heuristic, which path matches ignore.
+pub(crate) fn add_match_inside<'a>(
+    state: &'a mut MatchingState,
+    path: &Path,
+    comment: &str,
+) -> &'a mut MatchParameters {
+    match path.file_name() {
+        Some(name) if path.parent() == Some(state.path()) => {
+            let weight = if name.to_string_lossy().starts_with('.') { 2000 } else { MatchParameters::DEFAULT_WEIGHT };
+            state.add_match(name, comment).weight(weight)
+        },
+        _ => state.add_match_path(path, comment),
+    }
+}
+
+thread_local! {
+    /// Files read by the heuristics on this thread, see [`read_to_string()`].
+    static READ_FILES: RefCell<Vec<(PathBuf, Option<SystemTime>)>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Reads a file like [`fs::read_to_string()`], recording its path and modification time,
+/// so that the scan index can detect when the results of the heuristics become outdated.
+///
+/// The modification time is read first, so that changes made while reading are detected by the next scan.
+pub(crate) fn read_to_string<P: AsRef<Path>>(path: P) -> io::Result<String> {
+    let path = path.as_ref();
+    let mtime = file_mtime(path);
+    READ_FILES.with_borrow_mut(|files| {
+        if !files.iter().any(|(file, _)| file == path) {
+            files.push((path.to_owned(), mtime));
+        }
+    });
+    fs::read_to_string(path)
+}
+
+/// Returns the files read by the heuristics on this thread since the last call, see [`read_to_string()`].
+pub(crate) fn take_read_files() -> Vec<(PathBuf, Option<SystemTime>)> {
+    READ_FILES.take()
+}
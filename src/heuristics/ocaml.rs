@@ -0,0 +1,10 @@
+use crate::heuristic;
+
+heuristic!(OCaml, "", "ml", IconColor(208), state, {
+    if state.has_file("dune-project").is_some() && state.has_directory("_build").is_some() {
+        state.add_match("_build", "Found dune-project and _build directory.");
+    }
+    if state.has_directory("_opam").is_some_and(|opam| opam.join(".opam-switch").is_dir()) {
+        state.add_match("_opam", "Found local opam switch.");
+    }
+});
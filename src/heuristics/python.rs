@@ -1,8 +1,64 @@
 use crate::heuristic;
+use regex::Regex;
+use std::{fs, path::Path, sync::LazyLock};
 
+/// Weight of caches, which are safe to delete, high enough to outweigh the `Hidden` heuristic.
+const CACHE_WEIGHT: i32 = 2000;
+/// Weight of built distributables and metadata, which may still be wanted,
+/// so that they are skipped if any other heuristic (e.g. `Git`) is against deleting them.
+const DISTRIBUTABLE_WEIGHT: i32 = 500;
+
+/// Caches of Python tools, named specifically enough to be matched anywhere.
+const CACHES: [(&str, &str); 6] = [
+    (".pytest_cache", "Found pytest cache."),
+    (".mypy_cache", "Found mypy cache."),
+    (".ruff_cache", "Found Ruff cache."),
+    (".hypothesis", "Found Hypothesis database."),
+    (".tox", "Found tox environments."),
+    (".nox", "Found nox environments."),
+];
+
+/// Metadata directories of packages built with setuptools.
+static EGG_INFO: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\.egg-info$").expect("Regex error :)"));
+
+/// Contents of setuptools build directories.
+static SETUPTOOLS_BUILD: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(lib|bdist)(\..*)?$").expect("Regex error :)"));
+
+/// Built distributions of Python packages.
+static DISTRIBUTIONS: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\.(whl|tar\.gz|zip|egg)$").expect("Regex error :)"));
+
+/// Returns true if any entry in the directory matches the given regex.
+fn contains(dir: &Path, re: &Regex) -> bool {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return false;
+    };
+    entries.flatten().any(|entry| entry.file_name().to_str().is_some_and(|name| re.is_match(name)))
+}
 
 heuristic!(Python, "", "py", IconColor(32), state, {
     if state.has_directory("__pycache__").is_some() {
         state.add_match("__pycache__", "Found __pycache__.");
     }
+    for (dir, comment) in CACHES {
+        if state.has_directory(dir).is_some() {
+            state.add_match(dir, comment).weight(CACHE_WEIGHT);
+        }
+    }
+    for path in state.match_directory(&EGG_INFO) {
+        let name = path.file_name().expect("Expected path");
+        state.add_match(name, "Found egg-info metadata.").weight(DISTRIBUTABLE_WEIGHT);
+    }
+
+    if state.has_file("pyproject.toml").is_none() && state.has_file("setup.py").is_none() {
+        return;
+    }
+    // Both names are too common, so their contents are checked as well
+    if state.has_directory("build").is_some_and(|path| contains(&path, &SETUPTOOLS_BUILD)) {
+        state.add_match("build", "Found Python project and setuptools build directory.").weight(DISTRIBUTABLE_WEIGHT);
+    }
+    if state.has_directory("dist").is_some_and(|path| contains(&path, &DISTRIBUTIONS)) {
+        state.add_match("dist", "Found Python project and built distributions.").weight(DISTRIBUTABLE_WEIGHT);
+    }
 });
@@ -1,7 +1,290 @@
-use crate::heuristic;
+use super::{normalize, read_to_string};
+use crate::{core::days_since, heuristic};
+use std::{
+    collections::HashMap,
+    env, fs,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+use toml_edit::DocumentMut;
+
+/// Parses a TOML file, returning [`None`] if it can't be read or is invalid.
+fn read_toml(path: &Path) -> Option<DocumentMut> {
+    read_to_string(path).ok()?.parse().ok()
+}
+
+/// Returns the target directory set by `CARGO_TARGET_DIR` for the workspace in `root`, if any.
+///
+/// It comes from the environment of the cleaner, which may differ from the one the scanned projects
+/// are built in, so the directory must be verified to be a target directory before matching it.
+/// Relative paths are resolved against the workspace root, where Cargo is usually run.
+fn environment_target_dir(root: &Path) -> Option<PathBuf> {
+    let target_dir = env::var_os("CARGO_TARGET_DIR").filter(|dir| !dir.is_empty())?;
+    Some(normalize(&root.join(target_dir)))
+}
+
+/// Returns true if the directory has been created by Cargo, which tags all its target directories.
+fn is_target_dir(path: &Path) -> bool {
+    path.join("CACHEDIR.TAG").is_file()
+}
+
+/// Returns the root of the workspace containing the package in `dir` with the given manifest,
+/// which is the package directory itself if it's not a member of any other workspace.
+///
+/// Membership is simplified to the nearest ancestor with a `[workspace]` section not excluding the package,
+/// unless the workspace is explicitly selected in the `package.workspace` key.
+fn workspace_root(dir: &Path, manifest: &DocumentMut) -> PathBuf {
+    if manifest.contains_key("workspace") {
+        return dir.to_owned();
+    }
+    if let Some(workspace) = manifest.get("package").and_then(|package| package.get("workspace")?.as_str()) {
+        return normalize(&dir.join(workspace));
+    }
+    dir.ancestors()
+        .skip(1)
+        .find(|ancestor| {
+            let Some(workspace) = read_toml(&ancestor.join("Cargo.toml")) else {
+                return false;
+            };
+            let Some(workspace) = workspace.get("workspace") else {
+                return false;
+            };
+            let excluded = workspace.get("exclude").and_then(|exclude| exclude.as_array()).is_some_and(|exclude| {
+                exclude.iter().filter_map(|path| path.as_str()).any(|path| dir.starts_with(ancestor.join(path)))
+            });
+            !excluded
+        })
+        .map_or_else(|| dir.to_owned(), Path::to_owned)
+}
+
+/// Returns the target directory of the workspace in `root` with the source of its configuration,
+/// or [`None`] if it's not configured and defaults to `target`.
+///
+/// `build.target-dir` is searched in Cargo configs from the workspace root up the ancestor chain
+/// and then in `CARGO_HOME`. `CARGO_TARGET_DIR` is handled separately by [`environment_target_dir()`].
+fn configured_target_dir(root: &Path) -> Option<(PathBuf, String)> {
+    let cargo_dirs = root.ancestors().map(|ancestor| ancestor.join(".cargo"));
+    let cargo_home = env::var_os("CARGO_HOME").map(PathBuf::from);
+    cargo_dirs.chain(cargo_home).find_map(|cargo_dir| {
+        ["config.toml", "config"].into_iter().map(|name| cargo_dir.join(name)).find_map(|config| {
+            let target_dir = read_toml(&config)?.get("build")?.get("target-dir")?.as_str()?.to_owned();
+            // Relative paths are relative to the parent of the `.cargo` directory
+            let base = cargo_dir.parent().unwrap_or(&cargo_dir);
+            Some((normalize(&base.join(target_dir)), config.display().to_string()))
+        })
+    })
+}
+
+/// Returns the version of rustc which has last used the target directory and whether it is still installed,
+/// based on the output of `rustc -vV` and `rustc --print sysroot` cached by Cargo in `.rustc_info.json`.
+fn last_toolchain(target_dir: &Path) -> Option<(String, bool)> {
+    let info: serde_json::Value =
+        serde_json::from_str(&read_to_string(target_dir.join(".rustc_info.json")).ok()?).ok()?;
+    let lines: Vec<_> = info
+        .get("outputs")?
+        .as_object()?
+        .values()
+        .filter_map(|output| output.get("stdout")?.as_str())
+        .flat_map(str::lines)
+        .collect();
+    let version = lines.iter().find_map(|line| line.strip_prefix("rustc "))?.to_owned();
+    // The sysroot is the only absolute path printed
+    let installed = lines.iter().map(Path::new).find(|line| line.is_absolute()).map_or(true, Path::is_dir);
+    Some((version, installed))
+}
+
+/// Build artifacts of a single compilation unit, read from its fingerprint directory.
+#[derive(Default)]
+struct Artifact {
+    /// Name of the fingerprint directory, i.e. the name of the unit and its hash, e.g. `foo-0123456789abcdef`.
+    unit: String,
+    /// Hash of the rustc version used to build the artifact, read from its fingerprint.
+    rustc: Option<u64>,
+    /// Newest modification time of the fingerprint files, updated whenever the unit is rebuilt.
+    built: Option<SystemTime>,
+}
+
+/// Returns the hash of a file or directory created by Cargo for a unit, e.g. `0123456789abcdef` for
+/// `foo-0123456789abcdef` or `libfoo-0123456789abcdef.rlib`.
+fn unit_hash(path: &Path) -> Option<&str> {
+    let name = path.file_name()?.to_str()?;
+    let stem = name.split_once('.').map_or(name, |(stem, _)| stem);
+    let (_, hash) = stem.rsplit_once('-')?;
+    (hash.len() == 16 && hash.bytes().all(|b| b.is_ascii_hexdigit())).then_some(hash)
+}
+
+/// Collects all the artifacts of a profile directory (e.g. `target/debug`) from their fingerprints.
+fn profile_artifacts(profile: &Path) -> Vec<Artifact> {
+    let read_dir = |dir: &Path| fs::read_dir(dir).into_iter().flatten().flatten().map(|e| e.path());
+    read_dir(&profile.join(".fingerprint"))
+        .filter(|fingerprint| unit_hash(fingerprint).is_some())
+        .map(|fingerprint| {
+            let mut artifact = Artifact {
+                unit: fingerprint.file_name().unwrap_or_default().to_string_lossy().into_owned(),
+                ..Artifact::default()
+            };
+            // Not read with `read_to_string()` of the heuristics, as the target directory is never indexed
+            for file in read_dir(&fingerprint) {
+                let modified = file.metadata().and_then(|metadata| metadata.modified()).ok();
+                artifact.built = artifact.built.max(modified);
+                if file.extension().is_some_and(|extension| extension == "json") {
+                    let json = fs::read_to_string(&file).ok().and_then(|s| serde_json::from_str(&s).ok());
+                    if let Some(rustc) = json.and_then(|json: serde_json::Value| json.get("rustc")?.as_u64()) {
+                        artifact.rustc = Some(rustc);
+                    }
+                }
+            }
+            artifact
+        })
+        .collect()
+}
+
+/// Returns all the files and directories of the units in a profile directory, by the hashes of the units.
+///
+/// These are their fingerprints, outputs of their build scripts and all their files in `deps`.
+fn unit_paths(profile: &Path) -> HashMap<String, Vec<PathBuf>> {
+    let mut paths: HashMap<_, Vec<_>> = HashMap::new();
+    for dir in [".fingerprint", "build", "deps"] {
+        for path in fs::read_dir(profile.join(dir)).into_iter().flatten().flatten().map(|entry| entry.path()) {
+            if let Some(hash) = unit_hash(&path) {
+                paths.entry(hash.to_owned()).or_default().push(path);
+            }
+        }
+    }
+    paths
+}
+
+/// Adds stale parts of a Cargo target directory as partial matches, grouped with the project in `group`,
+/// so that they may be cleaned without forcing a full rebuild, like [cargo-sweep](https://github.com/holmgr/cargo-sweep) does.
+///
+/// These are incremental compilation caches and units built by toolchains other than the last used one
+/// (or by any toolchain if it is no longer installed) or not rebuilt for [`MatchingState::stale_artifact_days()`].
+/// Profiles containing only stale units are matched as a whole, otherwise every file of the stale units is.
+fn sweep(state: &mut MatchingState, target_dir: &Path, group: &Path) {
+    let toolchain = last_toolchain(target_dir);
+    // Profiles are either direct children of the target directory, or nested in target triple directories
+    let profiles: Vec<_> = fs::read_dir(target_dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .flat_map(|dir| {
+            let triple_profiles = fs::read_dir(&dir).into_iter().flatten().flatten().map(|entry| entry.path());
+            triple_profiles.chain([dir]).collect::<Vec<_>>()
+        })
+        .filter(|dir| dir.join(".fingerprint").is_dir())
+        .map(|dir| {
+            let artifacts = profile_artifacts(&dir);
+            (dir, artifacts)
+        })
+        .collect();
+    // Artifacts built by an unknown toolchain say nothing about which one was used last
+    let last_rustc = profiles
+        .iter()
+        .flat_map(|(_, artifacts)| artifacts)
+        .filter(|artifact| artifact.rustc.is_some())
+        .max_by_key(|artifact| artifact.built)
+        .and_then(|artifact| artifact.rustc);
+    let days = state.stale_artifact_days();
+
+    let uninstalled = matches!(toolchain, Some((_, false)));
+    let other_toolchain = |artifact: &&Artifact| artifact.rustc != last_rustc;
+    let old = |artifact: &&Artifact| artifact.built.and_then(days_since).is_some_and(|d| d >= days);
+    // Artifacts built by an unknown toolchain are never considered stale
+    let stale =
+        |artifact: &&Artifact| artifact.rustc.is_some() && (uninstalled || other_toolchain(artifact) || old(artifact));
+    // Describes why all the given artifacts are stale, if they are for the same reason
+    let reason = |artifacts: &[&Artifact]| match &toolchain {
+        Some((version, false)) if !artifacts.iter().any(other_toolchain) => {
+            Some(format!("built by rustc {version}, which is no longer installed"))
+        },
+        _ if artifacts.iter().all(other_toolchain) => match &toolchain {
+            Some((version, _)) => Some(format!("built by a different toolchain than the last used rustc {version}")),
+            None => Some("built by a different toolchain than the last used one".to_owned()),
+        },
+        _ if artifacts.iter().all(old) => Some(format!("not rebuilt for at least {days} days")),
+        _ => None,
+    };
+
+    for (profile, artifacts) in &profiles {
+        let artifacts: Vec<_> = artifacts.iter().collect();
+        if !artifacts.is_empty() && artifacts.iter().all(stale) {
+            let comment = reason(&artifacts).map_or_else(
+                || "Found Cargo profile containing only stale artifacts.".to_owned(),
+                |reason| format!("Found Cargo profile {reason}."),
+            );
+            state.add_match_path(profile, &comment).partial().custom_group(group.to_owned());
+            continue;
+        }
+
+        let incremental = profile.join("incremental");
+        if incremental.is_dir() {
+            state
+                .add_match_path(&incremental, "Found incremental compilation cache in Cargo target directory.")
+                .partial()
+                .custom_group(group.to_owned());
+        }
+        let mut paths = unit_paths(profile);
+        for artifact in artifacts.into_iter().filter(stale) {
+            let Some(files) = unit_hash(Path::new(&artifact.unit)).and_then(|hash| paths.remove(hash)) else {
+                continue;
+            };
+            let comment = reason(&[artifact]).map_or_else(
+                || format!("Found stale artifacts of Cargo unit {}.", artifact.unit),
+                |reason| format!("Found artifacts of Cargo unit {} {reason}.", artifact.unit),
+            );
+            for file in files {
+                state.add_match_path(&file, &comment).partial().custom_group(group.to_owned());
+            }
+        }
+    }
+}
 
 heuristic!(Rust, "", "rs", IconColor(166), state, {
-    if state.has_file("Cargo.toml").is_some() && state.has_directory("target").is_some() {
+    let Some(manifest) = state.has_file("Cargo.toml") else {
+        return;
+    };
+    let dir = state.path().to_owned();
+    let root = read_toml(&manifest).map_or_else(|| dir.clone(), |manifest| workspace_root(&dir, &manifest));
+
+    if root != dir {
+        // Workspace members share the target directory of their workspace
+        if let Some(target) = state.has_directory("target") {
+            state
+                .add_match("target", "Found Cargo.toml of a workspace member and its own target directory.")
+                .custom_group(root.clone());
+            sweep(state, &target, &root);
+        }
+        let target_dir = configured_target_dir(&root).map_or_else(|| root.join("target"), |(path, _)| path);
+        if is_target_dir(&target_dir) {
+            state
+                .add_match_path(
+                    &target_dir,
+                    "Found Cargo.toml of a workspace member and target directory of its workspace.",
+                )
+                .custom_group(root);
+        }
+        return;
+    }
+
+    if let Some(target) = state.has_directory("target") {
         state.add_match("target", "Found Cargo.toml and target directory.");
+        sweep(state, &target, &dir);
+    }
+    if let Some((target_dir, source)) = configured_target_dir(&dir) {
+        if target_dir != dir.join("target") && is_target_dir(&target_dir) {
+            state
+                .add_match_path(&target_dir, &format!("Found Cargo.toml and target directory configured in {source}."));
+            sweep(state, &target_dir, &dir);
+        }
+    }
+    if let Some(target_dir) = environment_target_dir(&dir) {
+        if target_dir != dir.join("target") && is_target_dir(&target_dir) {
+            state.add_match_path(
+                &target_dir,
+                "Found Cargo.toml and target directory set by CARGO_TARGET_DIR in the environment of the cleaner.",
+            );
+            sweep(state, &target_dir, &dir);
+        }
     }
 });
@@ -0,0 +1,7 @@
+use crate::heuristic;
+
+heuristic!(Swift, "", "swift", IconColor(202), state, {
+    if state.has_file("Package.swift").is_some() && state.has_directory(".build").is_some() {
+        state.add_match(".build", "Found Package.swift and .build directory.").weight(2000);
+    }
+});
@@ -1,34 +1,33 @@
+use super::read_to_string;
 use crate::heuristic;
+use regex::Regex;
+use std::{path::Path, sync::LazyLock};
 
-const DIRS: [&str; 14] = [
-    "Library",
-    "library",
-    "Logs",
-    "logs",
-    "Obj",
-    "obj",
-    "Temp",
-    "temp",
-    "UserSettings",
-    "userSettings",
-    "MemoryCaptures",
-    "memoryCaptures",
-    "Recordings",
-    "recordings",
-];
+/// Directories generated by the editor, which may be named with any case depending on the Unity version.
+static DIRS: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)^(library|logs|obj|temp|usersettings|memorycaptures|recordings)$").expect("Regex error :)")
+});
+
+/// Returns the editor version the project was last opened with, read from `ProjectSettings/ProjectVersion.txt`.
+fn editor_version(project: &Path) -> Option<String> {
+    let version = read_to_string(project.join("ProjectSettings/ProjectVersion.txt")).ok()?;
+    let version = version.lines().find_map(|line| line.strip_prefix("m_EditorVersion:"))?.trim();
+    (!version.is_empty()).then(|| version.to_owned())
+}
 
 heuristic!(Unity, "󰚯", "unity", IconColor(15), state, {
-    if state.has_directory("Assets").is_some()
-        && state.has_directory("Packages").is_some()
-        && state.has_directory("ProjectSettings").is_some()
+    if state.has_directory("Assets").is_none()
+        || state.has_directory("Packages").is_none()
+        || state.has_directory("ProjectSettings").is_none()
     {
-        for dir in DIRS {
-            if state.has_directory(dir).is_some() {
-                state.add_match(
-                    dir,
-                    &format!("Found Assets, Packages, ProjectSettings and {dir} directory."),
-                );
-            }
-        }
+        return;
+    }
+    let found = match editor_version(state.path()) {
+        Some(version) => format!("Found Unity {version} project"),
+        None => "Found Assets, Packages, ProjectSettings".to_owned(),
+    };
+    for path in state.match_directory(&DIRS) {
+        let name = path.file_name().expect("Expected path");
+        state.add_match(name, &format!("{found} and {} directory.", name.to_string_lossy()));
     }
 });
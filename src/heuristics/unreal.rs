@@ -0,0 +1,32 @@
+use crate::heuristic;
+use regex::Regex;
+use std::sync::LazyLock;
+
+/// Directories generated next to projects, with the ones also generated next to plugins marked by `true`.
+///
+/// Plugins without `Source` are distributed prebuilt, so their binaries can't be rebuilt and are never matched.
+const DIRS: [(&str, bool); 4] =
+    [("Binaries", true), ("Intermediate", true), ("Saved", false), ("DerivedDataCache", false)];
+
+/// Unreal Engine project descriptors.
+static PROJECT: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\.uproject$").expect("Regex error :)"));
+
+/// Unreal Engine plugin descriptors.
+static PLUGIN: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\.uplugin$").expect("Regex error :)"));
+
+heuristic!(Unreal, "󰦯", "unreal", IconColor(252), state, {
+    let project = state.match_file(&PROJECT);
+    let mut plugin = state.match_file(&PLUGIN);
+    if state.has_directory("Source").is_none() {
+        plugin.clear();
+    }
+    let Some(file) = project.first().or(plugin.first()).and_then(|path| path.file_name()) else {
+        return;
+    };
+    let file = file.to_string_lossy().into_owned();
+    for (dir, in_plugins) in DIRS {
+        if (!project.is_empty() || in_plugins) && state.has_directory(dir).is_some() {
+            state.add_match(dir, &format!("Found {file} and {dir} directory."));
+        }
+    }
+});
@@ -1,9 +1,57 @@
+use super::read_to_string;
 use crate::heuristic;
+use std::{fs, path::Path};
+
+/// Returns the Python version of a virtual environment, read from its `pyvenv.cfg`.
+///
+/// The key differs between `venv` (`version`) and `virtualenv` or `uv` (`version_info`).
+fn venv_version(cfg: &Path) -> Option<String> {
+    let cfg = read_to_string(cfg).ok()?;
+    let value = |key: &str| {
+        cfg.lines().find_map(|line| {
+            let (name, value) = line.split_once('=')?;
+            (name.trim() == key).then(|| value.trim().to_owned())
+        })
+    };
+    ["version", "version_info"].into_iter().filter_map(value).find_map(|version| {
+        // `version_info` also contains the release level, e.g. `3.12.1.final.0`
+        let numbers: Vec<_> = version.split('.').take_while(|part| part.parse::<u32>().is_ok()).collect();
+        (!numbers.is_empty()).then(|| numbers.join("."))
+    })
+}
+
+/// Returns the Python version of a conda environment, read from the name of its installed package metadata,
+/// e.g. `conda-meta/python-3.12.1-h1234567_0.json`.
+fn conda_version(meta: &Path) -> Option<String> {
+    fs::read_dir(meta).ok()?.flatten().find_map(|entry| {
+        let name = entry.file_name().into_string().ok()?;
+        let version = name.strip_prefix("python-")?.split('-').next()?;
+        version.starts_with(|c: char| c.is_ascii_digit()).then(|| version.to_owned())
+    })
+}
 
 heuristic!(Venv, "", "venv", IconColor(42), state, {
-    if let Some(path) = state.has_directory("venv").or(state.has_directory("env")) {
-        if path.join("pyvenv.cfg").is_file() {
-            state.add_match(path.file_name().expect("Expected path"), "Found python venv.");
-        }
+    let dirs: Vec<_> =
+        state.get_all_contents().filter(|(_, file_type)| file_type.is_dir()).map(|(path, _)| path).collect();
+    for path in dirs {
+        let cfg = path.join("pyvenv.cfg");
+        let comment = if cfg.is_file() {
+            match venv_version(&cfg) {
+                Some(version) => format!("Found Python {version} virtual environment."),
+                None => "Found Python virtual environment.".to_owned(),
+            }
+        } else if path.join("conda-meta").is_dir() && !path.join("condabin").is_dir() {
+            // Base installations of conda (with `condabin`) contain all the other environments
+            match conda_version(&path.join("conda-meta")) {
+                Some(version) => format!("Found conda environment with Python {version}."),
+                None => "Found conda environment.".to_owned(),
+            }
+        } else {
+            continue;
+        };
+        let name = path.file_name().expect("Expected path");
+        // Hidden environments (e.g. `.venv`) need a higher weight to outweigh the `Hidden` heuristic
+        let weight = if name.to_string_lossy().starts_with('.') { 2000 } else { 1000 };
+        state.add_match(name, &comment).weight(weight);
     }
 });
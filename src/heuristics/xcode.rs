@@ -0,0 +1,19 @@
+use crate::heuristic;
+use regex::Regex;
+use std::sync::LazyLock;
+
+/// Xcode projects and workspaces.
+static PROJECTS: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\.(xcodeproj|xcworkspace)$").expect("Regex error :)"));
+
+heuristic!(Xcode, "", "xcode", IconColor(33), state, {
+    let projects = state.match_directory(&PROJECTS);
+    let Some(project) = projects.first().and_then(|path| path.file_name()) else {
+        return;
+    };
+    let project = project.to_string_lossy().into_owned();
+    for dir in ["DerivedData", "build"] {
+        if state.has_directory(dir).is_some() {
+            state.add_match(dir, &format!("Found {project} and {dir} directory."));
+        }
+    }
+});
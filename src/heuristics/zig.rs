@@ -0,0 +1,12 @@
+use crate::heuristic;
+
+heuristic!(Zig, "", "zig", IconColor(214), state, {
+    if state.has_file("build.zig").is_none() {
+        return;
+    }
+    for (dir, weight) in [(".zig-cache", 2000), ("zig-cache", 1000), ("zig-out", 1000)] {
+        if state.has_directory(dir).is_some() {
+            state.add_match(dir, &format!("Found build.zig and {dir} directory.")).weight(weight);
+        }
+    }
+});
@@ -2,11 +2,13 @@ pub mod core;
 pub use core::Scanner;
 
 mod heuristics;
-pub use heuristics::ALL_HEURISTICS;
+pub use heuristics::{ALL_HEURISTICS, GLOBAL_CACHE_HEURISTICS};
 
 #[cfg(feature = "cli")]
 pub mod args;
 #[cfg(feature = "cli")]
+pub mod diff;
+#[cfg(feature = "cli")]
 pub mod log;
 #[cfg(feature = "cli")]
 pub mod simple;
@@ -1,5 +1,10 @@
+use crate::core::StableHasher;
 use directories::ProjectDirs;
-use std::{path::PathBuf, sync::LazyLock};
+use std::{
+    hash::Hasher,
+    path::{Path, PathBuf},
+    sync::LazyLock,
+};
 use tracing_error::ErrorLayer;
 use tracing_subscriber::{self, layer::SubscriberExt, util::SubscriberInitExt, Layer};
 
@@ -24,6 +29,23 @@ pub fn get_data_dir() -> PathBuf {
     directory
 }
 
+fn root_file_name(root: &Path) -> String {
+    let mut hasher = StableHasher::new();
+    hasher.write(root.canonicalize().unwrap_or_else(|_| root.to_owned()).as_os_str().as_encoded_bytes());
+    format!("{:016x}.json", hasher.finish())
+}
+
+/// Returns the path of the scan index for the given root directory, see [`Scanner::index_path`](crate::Scanner::index_path).
+pub fn get_index_path(root: &Path) -> PathBuf {
+    get_data_dir().join("index").join(root_file_name(root))
+}
+
+/// Returns the path of the scan checkpoint for the given root directory,
+/// see [`Scanner::checkpoint_path`](crate::Scanner::checkpoint_path).
+pub fn get_checkpoint_path(root: &Path) -> PathBuf {
+    get_data_dir().join("checkpoint").join(root_file_name(root))
+}
+
 pub fn initialize_logging() -> std::io::Result<()> {
     let directory = get_data_dir();
     std::fs::create_dir_all(directory.clone())?;
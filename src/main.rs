@@ -1,10 +1,12 @@
 use clap::Parser;
-use project_cleaner::{args::{Args, AppResult}, log::initialize_logging, ui, simple};
+use project_cleaner::{args::{Args, AppResult}, log::initialize_logging, ui, simple, diff};
 
 fn main() -> AppResult<()> {
     let _ = initialize_logging();
     let args = Args::parse();
-    if args.no_ui {
+    if args.diff {
+        diff::run(args);
+    } else if args.no_ui {
         simple::run(args);
     } else {
         ui::run(args)?;
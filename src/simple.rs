@@ -1,23 +1,44 @@
-use crate::{core::MatchData, Scanner};
+use crate::{
+    core::{days_since, project_activity_parallel, remove_path, MatchData},
+    log::{get_checkpoint_path, get_index_path},
+    Scanner, GLOBAL_CACHE_HEURISTICS,
+};
 use std::{
+    collections::{HashMap, HashSet},
     env::current_dir,
     io::{stdin, stdout, Write},
+    sync::Arc,
+    time::Duration,
 };
 
+fn print_match(data: &MatchData) {
+    println!(
+        "{}{}{}{}",
+        if data.dangerous() { "(Dangerous!) " } else { "" },
+        if data.outside_root() { "(Outside of the scanned directory) " } else { "" },
+        if data.partial() { "(Partial) " } else { "" },
+        data.path.display()
+    );
+    for language in data.languages() {
+        println!("\t-> {}", language);
+    }
+}
+
 pub fn run(args: super::args::Args) {
     let directory = args.path.unwrap_or_else(|| {
         current_dir().expect("Cannot access current directory, run with -p <DIR> to select another")
     });
     let (sender, receiver) = std::sync::mpsc::channel::<MatchData>();
 
+    let stale = args.stale;
     let collector = move || {
         let mut collected = vec![];
         while let Ok(data) = receiver.recv() {
-            println!("{}{}", if data.dangerous() { "(Dangerous!) " } else { "" }, data.path.display());
-            for language in data.languages() {
-                println!("\t-> {}", language);
+            // Stale projects are printed only after checking their activity
+            if stale.is_none() {
+                print_match(&data);
             }
-            collected.push(data.path);
+            collected.push(data);
         }
         collected
     };
@@ -26,6 +47,20 @@ pub fn run(args: super::args::Args) {
     println!("Searching for files and directories to delete...");
     let mut scanner = Scanner::new(&directory, sender);
     scanner.dangerous = args.dangerous;
+    if let Some(days) = args.stale_artifacts {
+        scanner.stale_artifact_days = days;
+    }
+    if args.global_caches {
+        scanner.heuristics.extend(GLOBAL_CACHE_HEURISTICS);
+    }
+    scanner.index_path = (!args.no_cache).then(|| get_index_path(&directory));
+    let checkpoint_path = get_checkpoint_path(&directory);
+    if args.resume && !checkpoint_path.exists() {
+        println!("No stopped scan of this directory found, scanning everything...");
+    }
+    scanner.time_limit = args.time_limit.map(|minutes| Duration::from_secs(minutes * 60));
+    scanner.checkpoint_path = (args.time_limit.is_some() || args.resume).then(|| checkpoint_path.clone());
+    scanner.resume = args.resume;
     scanner.scan_with_progress().for_each(|progress| {
         if let Err(error) = progress {
             if let Some(path) = error.path() {
@@ -33,7 +68,30 @@ pub fn run(args: super::args::Args) {
             }
         }
     });
-    let results = handle.join().unwrap();
+    let mut results = handle.join().unwrap();
+    if args.time_limit.is_some() && checkpoint_path.exists() {
+        println!("Time limit reached, run again with --resume to scan the remaining directories.");
+    }
+    if let Some(stale) = stale {
+        println!("Checking project activity...");
+        let excluded: Arc<HashSet<_>> = Arc::new(results.iter().map(|data| data.path.clone()).collect());
+        let groups: HashSet<_> = results.iter().map(|data| data.group().to_owned()).collect();
+        let (sender, receiver) = std::sync::mpsc::channel();
+        project_activity_parallel(groups.into_iter().collect(), excluded, sender);
+        let activity: HashMap<_, _> = receiver.iter().collect();
+        results.retain(|data| {
+            let activity = activity.get(data.group()).copied().flatten();
+            activity.and_then(days_since).map_or(true, |days| days >= stale)
+        });
+        results.iter().for_each(print_match);
+    }
+    // Partial matches are deleted together with the matches containing them
+    let containing: Vec<_> = results.iter().filter(|data| !data.partial()).map(|data| data.path.clone()).collect();
+    let results: Vec<_> = results
+        .into_iter()
+        .filter(|data| !data.partial() || !containing.iter().any(|path| data.path.starts_with(path)))
+        .map(|data| data.path)
+        .collect();
     if results.is_empty() {
         println!("Found nothing, exiting...");
         return;
@@ -57,8 +115,7 @@ pub fn run(args: super::args::Args) {
     }
 
     for path in results {
-        let result = if path.is_dir() { std::fs::remove_dir_all(&path) } else { std::fs::remove_file(&path) };
-        if let Err(error) = result {
+        if let Err(error) = remove_path(&path) {
             println!("Failed to delete {} ({})", path.display(), error);
         } else {
             println!("Deleted {}", path.display());
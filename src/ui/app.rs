@@ -4,14 +4,19 @@ use super::{
 };
 use crate::{
     args::Args,
-    core::{dir_rm_parallel, dir_stats_parallel, DirStats, MatchData},
-    Scanner,
+    core::{dir_rm_parallel, project_activity_parallel, DirStats, MatchData, SizeEstimate, SizeKind},
+    log::{get_checkpoint_path, get_index_path},
+    Scanner, GLOBAL_CACHE_HEURISTICS,
 };
 use std::{
     env, error,
     path::PathBuf,
-    sync::mpsc::{Receiver, Sender},
+    sync::{
+        mpsc::{Receiver, TryRecvError},
+        Arc,
+    },
     thread::JoinHandle,
+    time::{Duration, SystemTime},
 };
 use throbber_widgets_tui::ThrobberState;
 use tracing::info;
@@ -19,6 +24,9 @@ use tui_scrollview::ScrollViewState;
 
 pub type AppResult<T> = std::result::Result<T, Box<dyn error::Error>>;
 
+/// Number of largest subdirectories and file extensions shown in the info popup for every match.
+const BREAKDOWN_SIZE: usize = 5;
+
 #[derive(Debug, PartialEq)]
 pub enum AppState {
     Scanning,
@@ -26,8 +34,6 @@ pub enum AppState {
     Done,
 }
 
-type Channel<T> = (Sender<T>, Receiver<T>);
-
 #[derive(Debug)]
 pub struct App {
     pub args: Args,
@@ -41,12 +47,16 @@ pub struct App {
     pub state: AppState,
     pub popup_state: PopUpState,
 
-    pub dir_stats_channel: Channel<(usize, DirStats)>,
+    pub stats_receiver: Receiver<(PathBuf, DirStats)>,
+    pub estimate_receiver: Receiver<(PathBuf, SizeEstimate)>,
+    pub activity_receiver: Receiver<(PathBuf, Option<SystemTime>)>,
     pub scanner_receiver: Receiver<MatchData>,
     pub handle: Vec<JoinHandle<()>>,
     pub del_handle: Vec<JoinHandle<()>>,
 
     pub info_path: Option<PathBuf>,
+    /// True if the last scan was stopped by its time limit, leaving a checkpoint to resume from.
+    pub stopped: bool,
 }
 
 impl App {
@@ -56,20 +66,37 @@ impl App {
         let (sender, receiver) = std::sync::mpsc::channel();
         let mut scanner = Scanner::new(&root_path, sender);
         scanner.dangerous = args.dangerous;
+        if let Some(days) = args.stale_artifacts {
+            scanner.stale_artifact_days = days;
+        }
+        if args.global_caches {
+            scanner.heuristics.extend(GLOBAL_CACHE_HEURISTICS);
+        }
+        scanner.index_path = (!args.no_cache).then(|| get_index_path(&root_path));
+        scanner.breakdown = Some(BREAKDOWN_SIZE);
+        scanner.time_limit = args.time_limit.map(|minutes| Duration::from_secs(minutes * 60));
+        // Matches are collected for the checkpoint only if it may be needed
+        scanner.checkpoint_path = (args.time_limit.is_some() || args.resume).then(|| get_checkpoint_path(&root_path));
+        scanner.resume = args.resume;
+        let size_kind = if args.apparent_size { SizeKind::Apparent } else { SizeKind::Disk };
+        let table = TableData::new(size_kind, args.stale);
         Self {
             args,
             running: true,
             scanner,
-            table: TableData::default(),
+            table,
             throbber_state: ThrobberState::default(),
             scroll_state: ScrollViewState::new(),
             state: AppState::Scanning,
             popup_state: PopUpState::Closed,
-            dir_stats_channel: std::sync::mpsc::channel(),
+            stats_receiver: std::sync::mpsc::channel().1,
+            estimate_receiver: std::sync::mpsc::channel().1,
+            activity_receiver: std::sync::mpsc::channel().1,
             scanner_receiver: receiver,
             handle: vec![],
             del_handle: vec![],
             info_path: None,
+            stopped: false,
         }
     }
 
@@ -77,7 +104,14 @@ impl App {
         self.state = AppState::Scanning;
         self.handle = vec![];
 
-        let scanner = self.scanner.clone();
+        let (stats_sender, stats_receiver) = std::sync::mpsc::channel();
+        self.stats_receiver = stats_receiver;
+        let (estimate_sender, estimate_receiver) = std::sync::mpsc::channel();
+        self.estimate_receiver = estimate_receiver;
+        let mut scanner = self.scanner.clone();
+        scanner.stats_sender = Some(stats_sender);
+        scanner.estimate_sender = Some(estimate_sender);
+        scanner.inode_registry = Some(self.table.inodes.clone());
         let handle = std::thread::spawn(|| scanner.scan());
         self.handle.push(handle);
     }
@@ -86,48 +120,67 @@ impl App {
     pub fn tick(&mut self) {
         self.throbber_state.calc_next();
 
+        // Stats and estimates have to be received before matches,
+        // so that every match they refer to is already in the table
+        let estimates: Vec<_> = self.estimate_receiver.try_iter().collect();
+        let mut stats = vec![];
+        let stats_finished = loop {
+            match self.stats_receiver.try_recv() {
+                Ok(data) => stats.push(data),
+                Err(TryRecvError::Empty) => break false,
+                Err(TryRecvError::Disconnected) => break true,
+            }
+        };
+
         while let Ok(data) = self.scanner_receiver.try_recv() {
             info!("UI got new match path: {:?}", data.path);
             self.table.add_match(data);
         }
 
+        for (path, estimate) in estimates {
+            self.table.update_estimate(&path, estimate);
+        }
         let mut updated = false;
-        while let Ok((idx, data)) = self.dir_stats_channel.1.try_recv() {
-            info!("UI got dir stats (idx={idx})");
-            let res = self.table.update_match(idx, data);
+        for (path, data) in stats {
+            info!("UI got dir stats for {:?}", path);
+            let res = self.table.update_match(&path, data);
             updated = updated || res;
         }
+        while let Ok((path, activity)) = self.activity_receiver.try_recv() {
+            info!("UI got project activity for {:?}", path);
+            self.table.update_activity(&path, activity);
+            updated = true;
+        }
         if updated {
-            self.table.sort();
-        }
-
-        if self.handle.iter().all(|h| h.is_finished()) {
-            self.handle = vec![];
-            self.state = match self.state {
-                AppState::Scanning => {
-                    info!(
-                        "UI scan finished with {} matches which created {} records.",
-                        self.table.idx - 1,
-                        self.table.data.len()
-                    );
-                    self.handle = dir_stats_parallel(
-                        self.table
-                            .data
-                            .clone()
-                            .into_iter()
-                            .flat_map(|ele| ele.matches)
-                            .map(|ele| (ele.idx, ele.path))
-                            .collect(),
-                        self.dir_stats_channel.0.clone(),
-                    );
-                    AppState::Calculating
-                },
-                AppState::Calculating => {
-                    info!("UI stats calculation finished.");
-                    AppState::Done
-                },
-                AppState::Done => AppState::Done,
-            }
+            self.table.refresh_space();
+        }
+        // Sorted once for the whole batch, which matters with hundreds of thousands of matches
+        self.table.sort_pending();
+
+        match self.state {
+            AppState::Scanning if self.handle.iter().all(|h| h.is_finished()) => {
+                info!(
+                    "UI scan finished with {} matches which created {} records.",
+                    self.table.idx,
+                    self.table.data.len()
+                );
+                self.stopped = self.scanner.time_limit.is_some()
+                    && self.scanner.checkpoint_path.as_ref().is_some_and(|path| path.exists());
+                let (activity_sender, activity_receiver) = std::sync::mpsc::channel();
+                self.activity_receiver = activity_receiver;
+                let matches = self.table.data.iter().flat_map(|ele| ele.matches.iter().map(|e| e.path.clone()));
+                self.handle = project_activity_parallel(
+                    self.table.data.iter().map(|ele| ele.group_path.clone()).collect(),
+                    Arc::new(matches.collect()),
+                    activity_sender,
+                );
+                self.state = AppState::Calculating;
+            },
+            AppState::Calculating if stats_finished && self.handle.iter().all(|h| h.is_finished()) => {
+                info!("UI stats calculation finished.");
+                self.state = AppState::Done;
+            },
+            _ => {},
         }
 
         if self.popup_state == PopUpState::Open(PopUpKind::Delete(DeletePopUpKind::Deleting))
@@ -184,7 +237,7 @@ impl App {
     }
 
     pub fn reload(&mut self) {
-        self.table = TableData::default();
+        self.table = TableData::new(self.table.size_kind, self.table.stale_days);
         self.popup_state = PopUpState::Closed;
         self.del_handle = vec![];
         self.run();
@@ -228,6 +281,10 @@ impl App {
         self.table.sort_toggle();
     }
 
+    pub fn toggle_size_kind(&mut self) {
+        self.table.toggle_size_kind();
+    }
+
     pub fn is_highlighted(&self) -> bool {
         self.table.state.selected().is_some()
     }
@@ -244,7 +301,11 @@ impl App {
     }
 
     pub fn confirm_delete(&mut self) {
-        self.del_handle = dir_rm_parallel(self.table.get_selected_path());
+        let paths = self.table.get_selected_path();
+        for path in &paths {
+            self.table.inodes.unregister(path);
+        }
+        self.del_handle = dir_rm_parallel(paths);
         self.popup_state = PopUpState::Open(PopUpKind::Delete(DeletePopUpKind::Deleting));
     }
 }
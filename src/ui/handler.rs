@@ -59,11 +59,7 @@ pub fn handle_key_events(key_event: KeyEvent, app: &mut App) -> AppResult<()> {
                 KeyCode::Down => app.list_down(),
 
                 // Reload
-                KeyCode::Char('r') => {
-                    if app.state == AppState::Done {
-                        app.reload()
-                    }
-                },
+                KeyCode::Char('r') if app.state == AppState::Done => app.reload(),
 
                 // Delete
                 KeyCode::Char('d') => app.delete(),
@@ -73,6 +69,8 @@ pub fn handle_key_events(key_event: KeyEvent, app: &mut App) -> AppResult<()> {
                 KeyCode::Char('s') => app.focus_sort(),
                 // Select
                 KeyCode::Char(' ') => app.toggle_select(),
+                // Size kind
+                KeyCode::Char('a') => app.toggle_size_kind(),
 
                 _ => {},
             }
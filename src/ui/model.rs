@@ -1,9 +1,10 @@
-use crate::core::{CommentedLang, DirStats, Lang, MatchData};
+use crate::core::{days_since, CommentedLang, DirStats, InodeRegistry, Lang, MatchData, SizeEstimate, SizeKind};
 use ratatui::widgets::TableState;
 use size::Size;
 use std::{
-    collections::HashSet,
+    collections::{HashMap, HashSet},
     path::{Path, PathBuf},
+    sync::Arc,
     time::SystemTime,
 };
 
@@ -13,25 +14,66 @@ pub struct MatchGroup {
     pub group_path: PathBuf,
     pub status: MatchDataUIStatus,
     pub matches: Vec<MatchDataUI>,
+    /// Last modification of the project files outside of the matches, see [`crate::core::project_activity()`].
+    pub activity: Option<SystemTime>,
+    /// Sum of stats of all the matches, cached for sorting.
+    stats: DirStats,
+    /// Sum of exact sizes and estimates of all the matches, only while any of them is estimated.
+    estimate: Option<SizeEstimate>,
 }
 
 impl MatchGroup {
-    pub fn stats(&self) -> DirStats {
-        self.matches.iter().map(|ele| ele.dir_stats).sum()
+    pub fn stats(&self) -> &DirStats {
+        &self.stats
     }
 
-    pub fn path(&self) -> PathBuf {
-        self.group_path.clone()
+    fn refresh_stats(&mut self) {
+        self.stats = self.matches.iter().map(|ele| &ele.dir_stats).sum();
+        let estimated = self.matches.iter().any(|ele| ele.dir_stats.disk_size.is_none() && ele.estimate.is_some());
+        self.estimate = estimated.then(|| {
+            self.matches
+                .iter()
+                .map(|ele| match (ele.dir_stats.apparent_size, ele.dir_stats.disk_size) {
+                    (Some(apparent_size), Some(disk_size)) => SizeEstimate {
+                        apparent_size,
+                        disk_size,
+                    },
+                    _ => ele.estimate.unwrap_or_default(),
+                })
+                .sum()
+        });
     }
 
-    pub fn size(&self) -> Size {
-        self.stats().size.unwrap_or(Size::from_bytes(0))
+    /// Returns true if the size of any match is only estimated, see [`SizeEstimate`].
+    #[inline]
+    pub fn is_estimated(&self) -> bool {
+        self.estimate.is_some()
+    }
+
+    /// Returns the exact size of the selected kind or its estimate, [`None`] if neither is known yet.
+    pub fn known_size(&self, kind: SizeKind) -> Option<Size> {
+        match &self.estimate {
+            Some(estimate) => Some(estimate.size(kind)),
+            None => self.stats.size(kind),
+        }
+    }
+
+    pub fn size(&self, kind: SizeKind) -> Size {
+        self.known_size(kind).unwrap_or(Size::from_bytes(0))
     }
 
     pub fn last_mod(&self) -> SystemTime {
         self.stats().last_mod.unwrap_or(SystemTime::UNIX_EPOCH)
     }
 
+    pub fn activity(&self) -> SystemTime {
+        self.activity.unwrap_or(SystemTime::UNIX_EPOCH)
+    }
+
+    pub fn activity_days(&self) -> Option<u64> {
+        days_since(self.activity?)
+    }
+
     pub fn lang(&self) -> String {
         self.get_icons().iter().map(|ele| ele.name).collect()
     }
@@ -46,10 +88,15 @@ impl MatchGroup {
 
 #[derive(Debug, Clone)]
 pub struct MatchDataUI {
-    pub idx: usize,
     pub path: PathBuf,
     pub dir_stats: DirStats,
+    /// Quick estimate of the size, replaced by [`Self::dir_stats`] once they are calculated.
+    pub estimate: Option<SizeEstimate>,
     pub lang: Vec<CommentedLang>,
+    /// See [`MatchData::outside_root()`].
+    pub outside_root: bool,
+    /// See [`MatchData::partial()`].
+    pub partial: bool,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -66,7 +113,24 @@ pub struct TableData {
     pub sort_by: Field,
     pub ascending: bool,
     pub selected: Field,
+    pub size_kind: SizeKind,
+    /// If set, groups with project activity newer than this number of days are hidden.
+    pub stale_days: Option<u64>,
+    /// Groups hidden because of [`Self::stale_days`], by group path.
+    hidden: HashMap<PathBuf, MatchGroup>,
+    /// Positions of groups in [`Self::data`], by group path. Rebuilt whenever the order changes.
+    positions: HashMap<PathBuf, usize>,
+    /// Group paths of all matches (including hidden ones), by match path.
+    match_groups: HashMap<PathBuf, PathBuf>,
+    /// True if any group has been added or updated since the last sort.
+    unsorted: bool,
+    /// Hardlinks found in all matches, shared with the scanner.
+    pub inodes: Arc<InodeRegistry>,
     cleanable_space: Size,
+    /// Registry generation, size kind, number of matches and number of hidden groups
+    /// [`Self::cleanable_space`] was calculated for.
+    cleanable_key: Option<(u64, SizeKind, usize, usize)>,
+    selected_space: Size,
 }
 
 #[derive(Debug, Clone, PartialEq, Copy, Default)]
@@ -76,43 +140,109 @@ pub enum Field {
     #[default]
     Size,
     LastMod,
+    Activity,
 }
 
 impl TableData {
+    pub fn new(size_kind: SizeKind, stale_days: Option<u64>) -> Self {
+        Self {
+            size_kind,
+            stale_days,
+            ..Self::default()
+        }
+    }
+
     pub fn add_match(&mut self, data: MatchData) {
         let path = data.group().to_owned();
 
         let ui_data = MatchDataUI {
-            idx: self.idx,
             path: data.path.clone(),
             dir_stats: DirStats::default(),
+            estimate: None,
             lang: data.languages().to_vec(),
+            outside_root: data.outside_root(),
+            partial: data.partial(),
         };
         self.idx += 1;
+        self.match_groups.insert(data.path.clone(), path.clone());
 
-        if let Some(record) = self.data.iter_mut().find(|ele| ele.group_path == path) {
+        if let Some(record) = self.get_group_mut(&path) {
             record.dangerous |= data.dangerous();
             record.matches.push(ui_data);
         } else {
+            self.positions.insert(path.clone(), self.data.len());
             self.data.push(MatchGroup {
                 dangerous: data.dangerous(),
                 group_path: path,
                 status: MatchDataUIStatus::Found,
                 matches: vec![ui_data],
+                activity: None,
+                stats: DirStats::default(),
+                estimate: None,
             });
-            self.sort();
+            self.unsorted = true;
         }
     }
 
-    pub fn update_match(&mut self, idx: usize, data: DirStats) -> bool {
-        if let Some(ele) = self.get_match_by_idx_mut(idx) {
-            ele.dir_stats = data;
-            if let Some(size) = data.size {
-                self.cleanable_space += size;
-                return true;
+    pub fn update_match(&mut self, path: &Path, data: DirStats) -> bool {
+        let has_size = data.size(self.size_kind).is_some();
+        self.update_match_with(path, |ele| ele.dir_stats = data) && has_size
+    }
+
+    /// Sets the size estimate of a match, shown until its stats are calculated.
+    pub fn update_estimate(&mut self, path: &Path, estimate: SizeEstimate) {
+        self.update_match_with(path, |ele| ele.estimate = Some(estimate));
+    }
+
+    /// Updates a match using `update`, refreshing stats of its group. Returns false if the match was not found.
+    fn update_match_with(&mut self, path: &Path, update: impl FnOnce(&mut MatchDataUI)) -> bool {
+        let Some(group_path) = self.match_groups.get(path).cloned() else {
+            return false;
+        };
+        let Some(group) = self.get_group_mut(&group_path) else {
+            return false;
+        };
+        let Some(ele) = group.matches.iter_mut().find(|ele| ele.path == path) else {
+            return false;
+        };
+        update(ele);
+        group.refresh_stats();
+        self.unsorted = true;
+        true
+    }
+
+    /// Sets project activity of a group, hiding it if it is not stale enough.
+    pub fn update_activity(&mut self, group_path: &Path, activity: Option<SystemTime>) {
+        let Some(&idx) = self.positions.get(group_path) else {
+            return;
+        };
+        self.data[idx].activity = activity;
+        self.unsorted = true;
+
+        let days = self.data[idx].activity_days();
+        if self.stale_days.is_some_and(|stale| days.is_some_and(|days| days < stale)) {
+            let mut group = self.data.remove(idx);
+            group.status = MatchDataUIStatus::Found;
+            self.hidden.insert(group.group_path.clone(), group);
+            self.refresh_positions();
+            match self.state.selected() {
+                _ if self.data.is_empty() => self.state.select(None),
+                Some(selected) if selected > idx || selected >= self.data.len() => {
+                    self.state.select(Some(selected.saturating_sub(1)))
+                },
+                _ => {},
             }
+            self.refresh_space();
+        }
+    }
+
+    /// Sorts the table only if any group has been added or updated since the last sort.
+    ///
+    /// Meant to be called once after receiving a batch of updates, instead of sorting after each of them.
+    pub fn sort_pending(&mut self) {
+        if self.unsorted {
+            self.sort();
         }
-        false
     }
 
     pub fn sort(&mut self) {
@@ -124,23 +254,30 @@ impl TableData {
         };
 
         match self.sort_by {
-            Field::Path => self.data.sort_by_key(MatchGroup::path),
-            Field::Lang => self.data.sort_by_key(MatchGroup::lang),
-            Field::Size => self.data.sort_by_key(MatchGroup::size),
+            Field::Path => self.data.sort_by(|a, b| a.group_path.cmp(&b.group_path)),
+            Field::Lang => self.data.sort_by_cached_key(MatchGroup::lang),
+            Field::Size => self.data.sort_by_key(|group| group.size(self.size_kind)),
             Field::LastMod => self.data.sort_by_key(MatchGroup::last_mod),
+            Field::Activity => self.data.sort_by_key(MatchGroup::activity),
         };
 
         if !self.ascending {
             self.data.reverse();
         }
+        self.refresh_positions();
+        self.unsorted = false;
 
         if let Some(path) = path {
-            if let Some(idx) = self.data.iter().position(|ele| ele.group_path == path) {
+            if let Some(&idx) = self.positions.get(&path) {
                 self.state.select(Some(idx))
             }
         }
     }
 
+    fn refresh_positions(&mut self) {
+        self.positions = self.data.iter().enumerate().map(|(idx, ele)| (ele.group_path.clone(), idx)).collect();
+    }
+
     pub fn sort_toggle(&mut self) {
         if self.selected == self.sort_by {
             self.ascending = !self.ascending;
@@ -151,12 +288,22 @@ impl TableData {
         self.sort();
     }
 
+    pub fn toggle_size_kind(&mut self) {
+        self.size_kind = match self.size_kind {
+            SizeKind::Disk => SizeKind::Apparent,
+            SizeKind::Apparent => SizeKind::Disk,
+        };
+        self.refresh_space();
+        self.sort();
+    }
+
     pub fn sort_left(&mut self) {
         self.selected = match self.selected {
             Field::Lang => Field::Size,
             Field::Path => Field::Lang,
             Field::LastMod => Field::Path,
-            Field::Size => Field::LastMod,
+            Field::Activity => Field::LastMod,
+            Field::Size => Field::Activity,
         };
     }
 
@@ -164,17 +311,22 @@ impl TableData {
         self.selected = match self.selected {
             Field::Lang => Field::Path,
             Field::Path => Field::LastMod,
-            Field::LastMod => Field::Size,
+            Field::LastMod => Field::Activity,
+            Field::Activity => Field::Size,
             Field::Size => Field::Lang,
         };
     }
 
     pub fn get_by_path(&self, path: &Path) -> Option<&MatchGroup> {
-        self.data.iter().find(|ele| ele.group_path == path)
+        self.data.get(*self.positions.get(path)?)
     }
 
-    pub fn get_match_by_idx_mut(&mut self, idx: usize) -> Option<&mut MatchDataUI> {
-        self.data.iter_mut().flat_map(|ele| ele.matches.iter_mut()).find(|ele| ele.idx == idx)
+    /// Returns a visible or hidden group with the given path.
+    fn get_group_mut(&mut self, path: &Path) -> Option<&mut MatchGroup> {
+        match self.positions.get(path) {
+            Some(&idx) => self.data.get_mut(idx),
+            None => self.hidden.get_mut(path),
+        }
     }
 
     pub fn toggle_select(&mut self) {
@@ -183,7 +335,8 @@ impl TableData {
             self.data[selected].status = match ele.status {
                 MatchDataUIStatus::Found => MatchDataUIStatus::Selected,
                 MatchDataUIStatus::Selected => MatchDataUIStatus::Found,
-            }
+            };
+            self.refresh_space();
         }
     }
 
@@ -200,22 +353,49 @@ impl TableData {
     }
 
     pub fn get_selected_path(&self) -> Vec<PathBuf> {
-        self.data
+        self.selected_matches().into_iter().map(Path::to_owned).collect()
+    }
+
+    /// Returns paths of all matches in selected groups, except for partial matches inside other selected matches,
+    /// which are deleted together with them anyway.
+    fn selected_matches(&self) -> Vec<&Path> {
+        let selected: Vec<_> = self
+            .data
             .iter()
             .filter(|ele| ele.status == MatchDataUIStatus::Selected)
-            .flat_map(|ele| ele.matches.iter().map(|e| e.path.clone()).collect::<Vec<_>>())
+            .flat_map(|ele| &ele.matches)
+            .collect();
+        selected
+            .iter()
+            .filter(|ele| {
+                !ele.partial || !selected.iter().any(|other| !other.partial && ele.path.starts_with(&other.path))
+            })
+            .map(|ele| ele.path.as_path())
             .collect()
     }
 
+    /// Recalculates cleanable and selected space, taking hardlinks shared between matches into account.
+    ///
+    /// Partial matches are not counted in the cleanable space, as they are parts of other matches,
+    /// and neither are groups hidden by the stale filter, as they can't be selected.
+    /// The cleanable space is recalculated only if any match has been added, any group has been hidden
+    /// or the registry has changed.
+    pub fn refresh_space(&mut self) {
+        let key = Some((self.inodes.generation(), self.size_kind, self.idx, self.hidden.len()));
+        if key != self.cleanable_key {
+            let all = self.data.iter().flat_map(|ele| &ele.matches);
+            self.cleanable_space =
+                self.inodes.freed_size(all.filter(|ele| !ele.partial).map(|ele| ele.path.as_path()), self.size_kind);
+            self.cleanable_key = key;
+        }
+        self.selected_space = self.inodes.freed_size(self.selected_matches(), self.size_kind);
+    }
+
     pub fn cleanable_space(&self) -> Size {
         self.cleanable_space
     }
 
     pub fn selected_space(&self) -> Size {
-        self.data
-            .iter()
-            .filter(|ele| ele.status == MatchDataUIStatus::Selected)
-            .flat_map(|ele| ele.matches.iter().filter_map(|e| e.dir_stats.size).collect::<Vec<_>>())
-            .fold(Size::from_bytes(0), |prev, current| prev + current)
+        self.selected_space
     }
 }
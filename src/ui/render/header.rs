@@ -43,6 +43,10 @@ pub fn render(app: &mut App, frame: &mut Frame, area: Rect) {
     match app.state {
         AppState::Scanning => make_spinner(app, frame, info_header[1], "Scanning..."),
         AppState::Calculating => make_spinner(app, frame, info_header[1], "Calculating..."),
+        AppState::Done if app.stopped => {
+            let logo = Paragraph::new("Time limit reached").alignment(Alignment::Center).fg(Color::Yellow);
+            frame.render_widget(logo, info_header[1]);
+        },
         AppState::Done => {
             let logo = Paragraph::new("Done!").alignment(Alignment::Center).fg(Color::Green);
             frame.render_widget(logo, info_header[1]);
@@ -6,9 +6,12 @@ use ratatui::{
 };
 use unicode_segmentation::UnicodeSegmentation;
 
-use crate::ui::{
-    app::{App, AppState},
-    popup::{PopUpKind, PopUpState},
+use crate::{
+    core::SizeKind,
+    ui::{
+        app::{App, AppState},
+        popup::{PopUpKind, PopUpState},
+    },
 };
 
 pub fn render(app: &mut App, frame: &mut Frame, area: Rect) {
@@ -53,6 +56,10 @@ fn construct_help(app: &App) -> Vec<String> {
             res.push((0, "Scroll [↑↓]"));
             res.push((7, "Sort [s]"));
             res.push((10, "Exit [q]"));
+            res.push((8, match app.table.size_kind {
+                SizeKind::Disk => "Apparent size [a]",
+                SizeKind::Apparent => "Disk usage [a]",
+            }));
 
             if app.state == AppState::Done {
                 res.push((9, "Reload [r]"))
@@ -73,6 +80,6 @@ fn construct_help(app: &App) -> Vec<String> {
         },
     };
 
-    res.sort_by(|a, b| a.0.cmp(&b.0));
+    res.sort_by_key(|a| a.0);
     res.iter().map(|e| format!(" {} ", e.1)).collect()
 }
@@ -1,6 +1,9 @@
-use std::path::PathBuf;
+use std::{cmp::Reverse, path::PathBuf, sync::Arc};
 
-use crate::ui::{app::App, model::MatchGroup};
+use crate::{
+    core::{Breakdown, InodeRegistry, SizeKind, SubdirSize},
+    ui::{app::App, model::MatchGroup},
+};
 use ratatui::{
     layout::{self, Constraint, Layout, Rect, Size},
     prelude::StatefulWidget,
@@ -26,24 +29,74 @@ pub fn render(app: &mut App, frame: &mut Frame, area: Rect) -> Option<()> {
     let layout = Layout::default().margin(2).constraints(vec![Constraint::Fill(1)]).split(area);
     let path = app.info_path.clone()?;
     let match_data = app.table.get_by_path(&path)?.clone();
-    let popup = InfoPopup::new(path, match_data, app.args.no_icons);
+    let popup = InfoPopup::new(path, match_data, app.args.no_icons, app.table.inodes.clone(), app.table.size_kind);
 
     frame.render_stateful_widget(popup, layout[0], &mut app.scroll_state);
     Some(())
 }
 
+/// Renders the largest subdirectories and file extensions of a match, sorted by the selected size.
+fn breakdown_lines(breakdown: &Breakdown, size_kind: SizeKind, style: Style) -> Vec<Line<'static>> {
+    fn subdir_lines(subdirs: &[SubdirSize], size_kind: SizeKind, style: Style, indent: usize) -> Vec<Line<'static>> {
+        let mut sorted: Vec<_> = subdirs.iter().collect();
+        sorted.sort_by_key(|subdir| Reverse(subdir.size(size_kind)));
+        sorted
+            .into_iter()
+            .flat_map(|subdir| {
+                let mut lines = vec![Line::from(vec![
+                    Span::from(format!("{}{}/ ", " ".repeat(indent), subdir.name)),
+                    Span::styled(format!("{}", subdir.size(size_kind)), style),
+                ])];
+                lines.append(&mut subdir_lines(&subdir.children, size_kind, style, indent + 2));
+                lines
+            })
+            .collect()
+    }
+
+    let mut lines = vec![];
+    if !breakdown.subdirs.is_empty() {
+        lines.push(Line::from("    Largest directories:"));
+        lines.append(&mut subdir_lines(&breakdown.subdirs, size_kind, style, 6));
+    }
+    if !breakdown.extensions.is_empty() {
+        lines.push(Line::from("    Largest file types:"));
+        let mut extensions: Vec<_> = breakdown.extensions.iter().collect();
+        extensions.sort_by_key(|ext| Reverse(ext.size(size_kind)));
+        lines.extend(extensions.into_iter().map(|ext| {
+            Line::from(vec![
+                Span::from(match &ext.extension {
+                    Some(extension) => format!("      .{} ", extension),
+                    None => "      (no extension) ".to_owned(),
+                }),
+                Span::styled(format!("{}", ext.size(size_kind)), style),
+            ])
+        }));
+    }
+    lines
+}
+
 struct InfoPopup {
     path: PathBuf,
     match_data: MatchGroup,
     no_icons: bool,
+    inodes: Arc<InodeRegistry>,
+    size_kind: SizeKind,
 }
 
 impl InfoPopup {
-    pub fn new(path: PathBuf, match_data: MatchGroup, no_icons: bool) -> Self {
+    pub fn new(
+        path: PathBuf,
+        match_data: MatchGroup,
+        no_icons: bool,
+        inodes: Arc<InodeRegistry>,
+        size_kind: SizeKind,
+    ) -> Self {
         InfoPopup {
             path,
             match_data,
             no_icons,
+            inodes,
+            size_kind,
         }
     }
 }
@@ -57,6 +110,17 @@ impl StatefulWidget for InfoPopup {
 
         let mut text = vec![
             Line::from(vec![Span::styled(self.path.to_str().unwrap(), Style::default().bold().fg(Color::Cyan))]),
+            Line::from(vec![
+                Span::from("Project activity: "),
+                Span::styled(
+                    if let Some(s) = self.match_data.activity_days() {
+                        format!("{}d ago", s)
+                    } else {
+                        "---".to_owned()
+                    },
+                    small_style,
+                ),
+            ]),
             Line::from(vec![]),
         ];
         let mut other: Vec<Line> = self
@@ -64,12 +128,30 @@ impl StatefulWidget for InfoPopup {
             .matches
             .iter()
             .flat_map(|match_ui| {
+                let lower_bound = if match_ui.dir_stats.is_incomplete() { "≥" } else { "" };
                 let mut res = vec![
                     Line::from(vec![Span::styled(format!("- {}", match_ui.path.display()), Style::default().bold())]),
                     Line::from(vec![
                         Span::from("    Size: "),
                         Span::styled(
-                            if let Some(s) = &match_ui.dir_stats.size { format!("{}", s) } else { "---".to_owned() },
+                            if let Some(s) = &match_ui.dir_stats.disk_size {
+                                format!("{}{}", lower_bound, s)
+                            } else if let Some(estimate) = &match_ui.estimate {
+                                format!("~{}", estimate.disk_size)
+                            } else {
+                                "---".to_owned()
+                            },
+                            small_style,
+                        ),
+                        Span::from("  Apparent: "),
+                        Span::styled(
+                            if let Some(s) = &match_ui.dir_stats.apparent_size {
+                                format!("{}{}", lower_bound, s)
+                            } else if let Some(estimate) = &match_ui.estimate {
+                                format!("~{}", estimate.apparent_size)
+                            } else {
+                                "---".to_owned()
+                            },
                             small_style,
                         ),
                         Span::from("  Last_mod: "),
@@ -82,7 +164,54 @@ impl StatefulWidget for InfoPopup {
                             small_style,
                         ),
                     ]),
+                    Line::from(vec![
+                        Span::from("    Files: "),
+                        Span::styled(format!("{}", match_ui.dir_stats.files), small_style),
+                        Span::from("  Dirs: "),
+                        Span::styled(format!("{}", match_ui.dir_stats.dirs), small_style),
+                    ]),
+                    Line::from(vec![
+                        Span::from("    Exclusive: "),
+                        Span::styled(
+                            if let Some(s) = self.inodes.exclusive_size(&match_ui.path, self.size_kind) {
+                                format!("{}", s)
+                            } else {
+                                "---".to_owned()
+                            },
+                            small_style,
+                        ),
+                        Span::from("  Shared: "),
+                        Span::styled(
+                            if let Some(s) = self.inodes.shared_size(&match_ui.path, self.size_kind) {
+                                format!("{}", s)
+                            } else {
+                                "---".to_owned()
+                            },
+                            small_style,
+                        ),
+                    ]),
                 ];
+                if match_ui.outside_root {
+                    res.push(Line::from(vec![Span::styled(
+                        "    Outside of the scanned directory.",
+                        Style::default().fg(Color::LightYellow),
+                    )]));
+                }
+                if match_ui.partial {
+                    res.push(Line::from(vec![Span::styled(
+                        "    Part of another match, deleting it keeps the rest.",
+                        Style::default().fg(Color::LightBlue),
+                    )]));
+                }
+                if match_ui.dir_stats.is_incomplete() {
+                    res.push(Line::from(vec![Span::styled(
+                        format!("    {} entries could not be read, sizes are lower bounds.", match_ui.dir_stats.errors),
+                        Style::default().fg(Color::LightYellow),
+                    )]));
+                }
+                if let Some(breakdown) = &match_ui.dir_stats.breakdown {
+                    res.append(&mut breakdown_lines(breakdown, self.size_kind, small_style));
+                }
 
                 for lang in &match_ui.lang {
                     res.push(Line::from(vec![Span::styled(
@@ -2,14 +2,18 @@ use ratatui::{
     layout::{Constraint, Rect},
     style::{Color, Modifier, Style, Stylize},
     text::{Line, Span},
-    widgets::{Block, BorderType, Cell, Row, Table},
+    widgets::{Block, BorderType, Cell, Row, Table, TableState},
     Frame,
 };
+use std::ops::Range;
 
-use crate::ui::{
-    app::App,
-    model::{Field, MatchDataUIStatus, TableData},
-    popup::{PopUpKind, PopUpState},
+use crate::{
+    core::SizeKind,
+    ui::{
+        app::App,
+        model::{Field, MatchDataUIStatus, TableData},
+        popup::{PopUpKind, PopUpState},
+    },
 };
 
 pub fn render(app: &mut App, frame: &mut Frame, area: Rect) {
@@ -19,38 +23,69 @@ pub fn render(app: &mut App, frame: &mut Frame, area: Rect) {
         Constraint::Length(3),
         Constraint::Length(9),
         Constraint::Length(8),
+        Constraint::Length(11),
     ];
-    let table_data = app.table.clone();
+    // Only the visible rows are built, as the table may contain hundreds of thousands of groups
+    let height = area.height.saturating_sub(3) as usize;
+    let (offset, end) = visible_window(&mut app.table, height);
+    let selected = app.table.state.selected();
+    let mut window_state = TableState::default().with_selected(selected.and_then(|s| s.checked_sub(offset)));
 
     let sort_focused = app.popup_state == PopUpState::Open(PopUpKind::Sort);
 
-    let table = Table::new(
-        table_data_to_rows(&table_data, app.args.no_icons, app.table.state.selected(), sort_focused),
-        widths,
-    )
-    .column_spacing(1)
-    .header(make_header(&table_data, sort_focused))
-    .block(Block::bordered().border_type(BorderType::Rounded));
+    let table =
+        Table::new(table_data_to_rows(&app.table, offset..end, app.args.no_icons, selected, sort_focused), widths)
+            .column_spacing(1)
+            .header(make_header(&app.table, sort_focused))
+            .block(Block::bordered().border_type(BorderType::Rounded));
 
-    frame.render_stateful_widget(table, area, &mut app.table.state);
+    frame.render_stateful_widget(table, area, &mut window_state);
 }
 
-fn make_header(table: &TableData, sort_focused: bool) -> Row {
-    let mut header_text =
-        vec!["Lang".to_owned(), "Path".to_owned(), "".to_owned(), "LastMod".to_owned(), "Size".to_owned()];
+/// Scrolls the table so that the selected row is visible, returning the range of rows which fit in `height`.
+fn visible_window(table: &mut TableData, height: usize) -> (usize, usize) {
+    let len = table.data.len();
+    let mut offset = table.state.offset();
+    if let Some(selected) = table.state.selected() {
+        if selected < offset {
+            offset = selected;
+        } else if selected >= offset + height {
+            offset = selected + 1 - height.max(1);
+        }
+    }
+    offset = offset.min(len.saturating_sub(height));
+    *table.state.offset_mut() = offset;
+    (offset, (offset + height).min(len))
+}
+
+fn make_header(table: &TableData, sort_focused: bool) -> Row<'_> {
+    let size_header = match table.size_kind {
+        SizeKind::Disk => "Size",
+        SizeKind::Apparent => "Apparent",
+    };
+    let mut header_text = vec![
+        "Lang".to_owned(),
+        "Path".to_owned(),
+        "".to_owned(),
+        "LastMod".to_owned(),
+        "Active".to_owned(),
+        size_header.to_owned(),
+    ];
 
     match table.sort_by {
         Field::Lang => header_text[0].push_str(if table.ascending { " ▲" } else { " ▼" }),
         Field::Path => header_text[1].push_str(if table.ascending { " ▲" } else { " ▼" }),
-        Field::Size => header_text[4].push_str(if table.ascending { " ▲" } else { " ▼" }),
+        Field::Size => header_text[5].push_str(if table.ascending { " ▲" } else { " ▼" }),
         Field::LastMod => header_text[3].push_str(if table.ascending { " ▲" } else { " ▼" }),
+        Field::Activity => header_text[4].push_str(if table.ascending { " ▲" } else { " ▼" }),
     }
 
     let selected_index = match table.selected {
         Field::Lang => 0,
         Field::Path => 1,
-        Field::Size => 4,
+        Field::Size => 5,
         Field::LastMod => 3,
+        Field::Activity => 4,
     };
 
     let header: Vec<_> = header_text
@@ -72,10 +107,18 @@ fn make_header(table: &TableData, sort_focused: bool) -> Row {
     Row::new(header).style(Style::default().add_modifier(Modifier::BOLD).bg(Color::Cyan))
 }
 
-fn table_data_to_rows(data: &TableData, no_icons: bool, selected: Option<usize>, sort_focused: bool) -> Vec<Row> {
-    data.data
+fn table_data_to_rows(
+    data: &TableData,
+    window: Range<usize>,
+    no_icons: bool,
+    selected: Option<usize>,
+    sort_focused: bool,
+) -> Vec<Row<'_>> {
+    let offset = window.start;
+    data.data[window]
         .iter()
         .enumerate()
+        .map(|(idx, ele)| (offset + idx, ele))
         .map(|(idx, ele)| {
             let icons: Vec<_> = ele
                 .get_icons()
@@ -97,7 +140,14 @@ fn table_data_to_rows(data: &TableData, no_icons: bool, selected: Option<usize>,
                 line.push(Span::styled(ele.group_path.display().to_string(), fg));
                 line.push(Span::styled(" {...}", Color::DarkGray));
             }
+            if ele.matches.iter().any(|e| e.outside_root) {
+                line.push(Span::styled(" (external)", Color::LightYellow));
+            }
+            if ele.matches.iter().all(|e| e.partial) {
+                line.push(Span::styled(" (partial)", Color::LightBlue));
+            }
 
+            let stats = ele.stats();
             Row::new(vec![
                 Cell::new(Line::from(icons)),
                 Cell::new(Line::from(line)).bg(if is_selected { Color::White } else { Color::Reset }),
@@ -106,16 +156,23 @@ fn table_data_to_rows(data: &TableData, no_icons: bool, selected: Option<usize>,
                 } else {
                     Span::from("")
                 })),
-                Cell::new(Span::from(if let Some(s) = &ele.stats().last_mod_days() {
+                Cell::new(Span::from(if let Some(s) = &stats.last_mod_days() {
                     format!("{}d", s)
                 } else {
                     "---".to_owned()
                 })),
-                Cell::new(Span::from(if let Some(s) = &ele.stats().size {
-                    format!("{}", s)
+                Cell::new(Span::from(if let Some(s) = &ele.activity_days() {
+                    format!("{}d", s)
                 } else {
                     "---".to_owned()
                 })),
+                Cell::new(match ele.known_size(data.size_kind) {
+                    Some(s) if ele.is_estimated() => Span::styled(format!("~{}", s), Color::Gray),
+                    // Some files were unreadable, so the real size may be bigger
+                    Some(s) if stats.is_incomplete() => Span::styled(format!("≥{}", s), Color::LightYellow),
+                    Some(s) => Span::from(format!("{}", s)),
+                    None => Span::from("---"),
+                }),
             ])
         })
         .collect()
@@ -0,0 +1,30 @@
+//! Helpers shared by the integration tests.
+#![allow(dead_code)]
+
+use project_cleaner::{core::MatchData, Scanner};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Creates an empty, unique directory for a test.
+pub fn test_directory(name: &str) -> PathBuf {
+    let directory = std::env::temp_dir().join(format!("project_cleaner_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&directory);
+    fs::create_dir_all(&directory).unwrap();
+    directory
+}
+
+/// Scans `root` with the default settings and returns the matches sorted by path.
+pub fn scan(root: &Path) -> Vec<MatchData> {
+    let (sender, receiver) = std::sync::mpsc::channel();
+    Scanner::new(root, sender).scan();
+    let mut matches: Vec<_> = receiver.iter().collect();
+    matches.sort_by(|a, b| a.path.cmp(&b.path));
+    matches
+}
+
+/// Scans `root` with the default settings and returns the sorted matched paths.
+pub fn scan_paths(root: &Path) -> Vec<PathBuf> {
+    scan(root).into_iter().map(|data| data.path).collect()
+}
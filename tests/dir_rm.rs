@@ -0,0 +1,30 @@
+mod common;
+
+use common::test_directory;
+use project_cleaner::core::remove_path;
+use std::fs;
+
+#[test]
+fn remove_read_only_tree() {
+    let directory = test_directory("read_only");
+    let module = directory.join("pkg/mod/example.com/dep@v1.0.0");
+    fs::create_dir_all(&module).unwrap();
+    fs::write(module.join("go.mod"), "module example.com/dep\n").unwrap();
+    // Like the Go module cache, which is read-only
+    for path in [module.join("go.mod"), module.clone(), module.parent().unwrap().to_owned()] {
+        let mut permissions = fs::metadata(&path).unwrap().permissions();
+        permissions.set_readonly(true);
+        fs::set_permissions(&path, permissions).unwrap();
+    }
+    if fs::write(module.join("probe"), "").is_ok() {
+        println!("Skipped, permissions are not enforced (e.g. when running as root)");
+        remove_path(&directory).unwrap();
+        return;
+    }
+
+    remove_path(&directory.join("pkg")).unwrap();
+    assert!(!directory.join("pkg").exists(), "read-only tree wasn't removed");
+    remove_path(&directory.join("pkg")).expect("removing a missing path should be skipped");
+
+    fs::remove_dir_all(directory).unwrap();
+}
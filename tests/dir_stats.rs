@@ -0,0 +1,154 @@
+mod common;
+
+use common::test_directory;
+use project_cleaner::{
+    core::{project_activity, project_activity_parallel, DirStats, InodeRegistry, SizeEstimate, SizeKind},
+    Scanner,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+#[test]
+#[cfg(unix)]
+fn hardlinks_shared_between_matches() {
+    let root = test_directory("hardlinks");
+    for project in ["first", "second"] {
+        fs::create_dir_all(root.join(project).join("node_modules")).unwrap();
+        fs::write(root.join(project).join("package.json"), "{}").unwrap();
+        fs::write(root.join(project).join("node_modules").join("own.js"), vec![b'a'; 1000]).unwrap();
+    }
+    let shared = root.join("first").join("node_modules").join("shared.js");
+    fs::write(&shared, vec![b'b'; 5000]).unwrap();
+    fs::hard_link(&shared, root.join("second").join("node_modules").join("shared.js")).unwrap();
+
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let (stats_sender, stats_receiver) = std::sync::mpsc::channel();
+    let registry = Arc::new(InodeRegistry::default());
+    let mut scanner = Scanner::new(&root, sender);
+    scanner.stats_sender = Some(stats_sender);
+    scanner.inode_registry = Some(registry.clone());
+    scanner.scan();
+    drop(receiver);
+
+    let stats: Vec<_> = stats_receiver.iter().collect();
+    assert_eq!(stats.len(), 2, "both node_modules directories should be matched");
+    let total_apparent: u64 = stats.iter().map(|(_, s)| s.apparent_size.unwrap().bytes() as u64).sum();
+    let freed = registry.total_freed_size(SizeKind::Apparent).bytes() as u64;
+    assert!(freed < total_apparent, "shared file was counted twice");
+
+    for (path, stats) in &stats {
+        let exclusive = registry.exclusive_size(path, SizeKind::Apparent).unwrap();
+        let shared = registry.shared_size(path, SizeKind::Apparent).unwrap();
+        assert_eq!(shared.bytes(), 5000, "shared file wasn't detected in {}", path.display());
+        assert_eq!(exclusive + shared, stats.apparent_size.unwrap(), "sizes don't add up in {}", path.display());
+    }
+    let both = registry.freed_size(stats.iter().map(|(path, _)| path.as_path()), SizeKind::Apparent);
+    assert_eq!(both.bytes() as u64, freed);
+
+    let generation = registry.generation();
+    registry.unregister(&stats[0].0);
+    assert_ne!(registry.generation(), generation);
+    assert_eq!(registry.exclusive_size(&stats[0].0, SizeKind::Apparent), None);
+    assert_eq!(
+        registry.total_freed_size(SizeKind::Apparent),
+        registry.exclusive_size(&stats[1].0, SizeKind::Apparent).unwrap()
+    );
+
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn missing_directory_stats() {
+    let directory = test_directory("missing");
+    let stats = DirStats::new(directory.join("does_not_exist"));
+    assert!(stats.is_incomplete(), "missing directory wasn't reported as an error");
+    assert_eq!(stats.disk_size, None);
+    assert_eq!(stats.apparent_size, None);
+
+    fs::remove_dir_all(directory).unwrap();
+}
+
+#[test]
+fn project_activity_ignores_matches() {
+    let root = test_directory("activity");
+    let target = root.join("target");
+    fs::create_dir_all(&target).unwrap();
+    let old = SystemTime::now() - Duration::from_secs(30 * 86400);
+    let source = fs::File::create(root.join("main.rs")).unwrap();
+    source.set_modified(old).unwrap();
+    fs::write(target.join("output"), "new").unwrap();
+
+    let activity = project_activity(&root, Arc::new(HashSet::from([target])));
+    assert_eq!(activity, Some(old), "modification inside of a match was counted as activity");
+
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn nested_project_activity() {
+    let root = test_directory("nested_activity");
+    let nested = root.join("nested");
+    fs::create_dir_all(nested.join("target")).unwrap();
+    let old = SystemTime::now() - Duration::from_secs(30 * 86400);
+    let older = old - Duration::from_secs(86400);
+    fs::File::create(root.join("main.rs")).unwrap().set_modified(older).unwrap();
+    fs::File::create(nested.join("main.rs")).unwrap().set_modified(old).unwrap();
+    fs::write(nested.join("target").join("output"), "new").unwrap();
+
+    // Matches may be groups of their own, e.g. shared caches, which are not projects
+    let target = nested.join("target");
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let excluded = Arc::new(HashSet::from([target.clone()]));
+    let handles = project_activity_parallel(vec![root.clone(), nested.clone(), target.clone()], excluded, sender);
+    handles.into_iter().for_each(|handle| handle.join().unwrap());
+    let activity: HashMap<_, _> = receiver.iter().collect();
+    assert_eq!(activity, HashMap::from([(root.clone(), Some(old)), (nested, Some(old)), (target, None)]));
+
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn largest_subdirectories_and_extensions() {
+    let target = test_directory("breakdown").join("target");
+    for dir in ["debug/incremental/crate-1", "debug/deps", "release"] {
+        fs::create_dir_all(target.join(dir)).unwrap();
+    }
+    fs::write(target.join("debug/incremental/crate-1/query-cache.bin"), vec![0; 30_000]).unwrap();
+    fs::write(target.join("debug/deps/libfoo.rlib"), vec![0; 20_000]).unwrap();
+    fs::write(target.join("release/libfoo.rlib"), vec![0; 5_000]).unwrap();
+    fs::write(target.join("CACHEDIR.TAG"), "").unwrap();
+
+    let stats = DirStats::with_breakdown(target.clone(), 1);
+    let breakdown = stats.breakdown.expect("breakdown wasn't calculated");
+    assert_eq!(breakdown.subdirs.len(), 1, "only the largest subdirectory should be retained");
+    assert_eq!(breakdown.subdirs[0].name, "debug");
+    assert!(breakdown.subdirs[0].apparent_size >= 50_000);
+    let children: Vec<_> = breakdown.subdirs[0].children.iter().map(|child| child.name.as_str()).collect();
+    assert_eq!(children, ["incremental"], "nested subdirectories are wrong");
+    assert!(breakdown.subdirs[0].children[0].children.is_empty(), "breakdown is too deep");
+    assert_eq!(breakdown.extensions.len(), 1);
+    assert_eq!(breakdown.extensions[0].extension.as_deref(), Some("bin"));
+
+    assert!(DirStats::new(target.clone()).breakdown.is_none());
+    fs::remove_dir_all(target.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn sampled_size_estimate() {
+    let directory = test_directory("estimate");
+    let modules = directory.join("node_modules");
+    for package in 0..100 {
+        fs::create_dir_all(modules.join(format!("package-{}", package))).unwrap();
+        fs::write(modules.join(format!("package-{}", package)).join("index.js"), vec![0; 1_000]).unwrap();
+    }
+
+    let estimate = SizeEstimate::sample(&modules);
+    assert!(estimate.apparent_size.bytes() >= 100_000, "files weren't counted in the estimate");
+    let stats = DirStats::new(modules.clone());
+    assert!(estimate.size(SizeKind::Disk) <= stats.disk_size.unwrap(), "estimate is bigger than the real size");
+    fs::remove_dir_all(directory).unwrap();
+}
@@ -0,0 +1,605 @@
+mod common;
+
+use common::{scan, scan_paths, test_directory};
+use project_cleaner::{core::project_activity_parallel, Scanner, GLOBAL_CACHE_HEURISTICS};
+use std::{
+    collections::HashSet,
+    fs::{self, File},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+#[test]
+fn rust_workspace_with_configured_target_dir() {
+    let directory = test_directory("rust_workspace");
+    let workspace = directory.join("workspace");
+    fs::create_dir_all(workspace.join(".cargo")).unwrap();
+    fs::create_dir_all(workspace.join("member/src")).unwrap();
+    fs::create_dir_all(directory.join("shared-target")).unwrap();
+    fs::write(workspace.join("Cargo.toml"), "[workspace]\nmembers = [\"member\"]\n").unwrap();
+    fs::write(workspace.join(".cargo/config.toml"), "[build]\ntarget-dir = \"../shared-target\"\n").unwrap();
+    fs::write(workspace.join("member/Cargo.toml"), "[package]\nname = \"member\"\n").unwrap();
+    fs::write(directory.join("shared-target/CACHEDIR.TAG"), "").unwrap();
+    // Where `../shared-target` would point if it was resolved against the current directory of a relative root
+    fs::create_dir_all(workspace.join("shared-target")).unwrap();
+    fs::write(workspace.join("shared-target/CACHEDIR.TAG"), "").unwrap();
+
+    // The root is relative to the current directory, which is the crate root during tests
+    let current = std::env::current_dir().unwrap();
+    let up: PathBuf = current.components().skip(1).map(|_| "..").collect();
+    let relative = up.join(workspace.strip_prefix(workspace.ancestors().last().unwrap()).unwrap());
+    let matches = scan(&relative);
+    assert_eq!(matches.len(), 1, "target directory should be found exactly once: {:#?}", matches);
+    assert_eq!(matches[0].path, directory.join("shared-target"));
+    assert_eq!(matches[0].group(), workspace, "target directory wasn't grouped with the workspace");
+    assert!(matches[0].outside_root(), "target directory wasn't flagged as outside of the scan");
+
+    fs::remove_dir_all(directory).unwrap();
+}
+
+#[test]
+fn rust_target_dir_from_environment() {
+    let directory = test_directory("rust_environment");
+    fs::create_dir_all(directory.join("tagged/env-target")).unwrap();
+    fs::create_dir_all(directory.join("untagged/env-target")).unwrap();
+    for project in ["tagged", "untagged"] {
+        fs::write(directory.join(project).join("Cargo.toml"), "[package]\nname = \"project\"\n").unwrap();
+    }
+    fs::write(directory.join("tagged/env-target/CACHEDIR.TAG"), "").unwrap();
+
+    // Relative, so that it doesn't point to any directory of the other tests running at the same time
+    std::env::set_var("CARGO_TARGET_DIR", "env-target");
+    let matches = scan(&directory);
+    assert_eq!(matches.len(), 1, "only the tagged target directory should be found: {:#?}", matches);
+    assert_eq!(matches[0].path, directory.join("tagged/env-target"));
+    let comment = &matches[0].languages().last().unwrap().comment;
+    assert!(comment.contains("environment"), "target directory wasn't flagged as set by the environment: {comment}");
+
+    fs::remove_dir_all(directory).unwrap();
+}
+
+#[test]
+fn rust_stale_artifacts_in_target_dir() {
+    let directory = test_directory("rust_sweep");
+    let target = directory.join("target");
+    let artifact = |profile: &str, name: &str, rustc: Option<u64>, days: u64| {
+        let fingerprint = target.join(profile).join(".fingerprint").join(name);
+        fs::create_dir_all(&fingerprint).unwrap();
+        let json = fingerprint.join("lib.json");
+        fs::write(&json, rustc.map_or("{".to_owned(), |rustc| format!("{{\"rustc\":{}}}", rustc))).unwrap();
+        let modified = SystemTime::now() - Duration::from_secs(days * 86400);
+        File::options().write(true).open(json).unwrap().set_modified(modified).unwrap();
+    };
+    artifact("debug", "fresh-0123456789abcdef", Some(1), 1);
+    artifact("debug", "other-1123456789abcdef", Some(2), 2);
+    // Newer than the rest, but can't tell which toolchain was used last
+    artifact("debug", "unknown-2123456789abcdef", None, 0);
+    artifact("release", "old-3123456789abcdef", Some(1), 100);
+    artifact("x86_64-unknown-linux-gnu/debug", "other-4123456789abcdef", Some(2), 2);
+    artifact("fuzz", "recent-5123456789abcdef", Some(1), 3);
+    artifact("check", "unknown-6123456789abcdef", None, 100);
+    fs::create_dir_all(target.join("debug/incremental/fresh-abc")).unwrap();
+    fs::create_dir_all(target.join("check/incremental")).unwrap();
+    fs::create_dir_all(target.join("debug/build/other-1123456789abcdef")).unwrap();
+    fs::create_dir_all(target.join("debug/deps")).unwrap();
+    for file in ["libother-1123456789abcdef.rlib", "other-1123456789abcdef.d", "libfresh-0123456789abcdef.rlib"] {
+        fs::write(target.join("debug/deps").join(file), "").unwrap();
+    }
+    fs::write(directory.join("Cargo.toml"), "[package]\nname = \"sweep\"\n").unwrap();
+
+    let partial: Vec<_> = scan(&directory)
+        .into_iter()
+        .filter(|data| data.partial())
+        .inspect(|data| assert_eq!(data.group(), directory, "partial match wasn't grouped with the project"))
+        .map(|data| data.path)
+        .collect();
+    let expected = [
+        "check/incremental",
+        "debug/.fingerprint/other-1123456789abcdef",
+        "debug/build/other-1123456789abcdef",
+        "debug/deps/libother-1123456789abcdef.rlib",
+        "debug/deps/other-1123456789abcdef.d",
+        "debug/incremental",
+        "release",
+        "x86_64-unknown-linux-gnu/debug",
+    ];
+    assert_eq!(partial, expected.map(|path| target.join(path)));
+
+    // Only units older than the configured number of days are stale, the rest of the profile is kept
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let mut scanner = Scanner::new(&directory, sender);
+    scanner.stale_artifact_days = 2;
+    scanner.scan();
+    let mut partial: Vec<_> = receiver.iter().filter(|data| data.partial()).map(|data| data.path).collect();
+    partial.sort();
+    assert!(partial.contains(&target.join("fuzz")), "profile older than the configured days wasn't found");
+    assert!(!partial.contains(&target.join("debug")), "profile with fresh units was found as a whole");
+
+    fs::remove_dir_all(directory).unwrap();
+}
+
+#[test]
+fn rust_incremental_cache_does_not_count_as_activity() {
+    let directory = test_directory("rust_activity");
+    let old = SystemTime::now() - Duration::from_secs(60 * 86400);
+    fs::create_dir_all(directory.join("src")).unwrap();
+    fs::create_dir_all(directory.join("target/debug/.fingerprint")).unwrap();
+    fs::create_dir_all(directory.join("target/debug/incremental/project-abc")).unwrap();
+    fs::write(directory.join("target/CACHEDIR.TAG"), "").unwrap();
+    fs::write(directory.join("target/debug/incremental/project-abc/query-cache.bin"), "new").unwrap();
+    for file in ["Cargo.toml", "src/main.rs"] {
+        fs::write(directory.join(file), "[package]\nname = \"project\"\n").unwrap();
+        File::options().write(true).open(directory.join(file)).unwrap().set_modified(old).unwrap();
+    }
+    File::open(directory.join("src")).unwrap().set_modified(old).unwrap();
+
+    let matches = scan(&directory);
+    assert!(matches.iter().any(|data| data.partial()), "incremental cache wasn't found: {:#?}", matches);
+    let groups: HashSet<_> = matches.iter().map(|data| data.group().to_owned()).collect();
+    assert_eq!(groups, HashSet::from([directory.clone()]), "matches weren't grouped with the project");
+    let excluded = Arc::new(matches.into_iter().map(|data| data.path).collect());
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let handles = project_activity_parallel(groups.into_iter().collect(), excluded, sender);
+    handles.into_iter().for_each(|handle| handle.join().unwrap());
+    let activity: Vec<_> = receiver.iter().collect();
+    assert_eq!(activity, [(directory.clone(), Some(old))], "fresh incremental cache was counted as activity");
+
+    fs::remove_dir_all(directory).unwrap();
+}
+
+#[test]
+fn maven_multi_module_project() {
+    let directory = test_directory("maven");
+    let pom = |dir: &str, xml: &str| {
+        fs::create_dir_all(directory.join(dir)).unwrap();
+        fs::write(directory.join(dir).join("pom.xml"), format!("<project>{xml}</project>")).unwrap();
+    };
+    pom(".", "<modules><module>core</module><module>app/pom.xml</module></modules>");
+    pom(
+        "core",
+        r"
+        <properties><output>build/maven</output></properties>
+        <build>
+            <!-- <directory>commented</directory> -->
+            <resources><resource><directory>src/resources</directory></resource></resources>
+            <directory>${project.basedir}/${output}</directory>
+        </build>",
+    );
+    pom("app", "<build><finalName>app</finalName></build>");
+    // Build directories outside of the project have to be created by Maven
+    pom("unrelated", "<build><directory>../core/src/resources</directory></build>");
+    for dir in ["target", "core/build/maven/classes", "core/src/resources", "app/target"] {
+        fs::create_dir_all(directory.join(dir)).unwrap();
+    }
+
+    let matches: Vec<_> = scan(&directory)
+        .into_iter()
+        .inspect(|data| assert_eq!(data.group(), directory, "module wasn't grouped with the project root"))
+        .map(|data| data.path)
+        .collect();
+    assert_eq!(matches, [directory.join("app/target"), directory.join("core/build/maven"), directory.join("target")]);
+
+    fs::remove_dir_all(directory).unwrap();
+}
+
+#[test]
+fn gradle_multi_project_build() {
+    let directory = test_directory("gradle");
+    let settings = r#"
+        rootProject.name = "root"
+        include(":app", ":lib:core")
+        // include(":commented")
+        include ':custom'
+        project(':custom').projectDir = file('modules/custom')
+    "#;
+    fs::write(directory.join("settings.gradle.kts"), settings).unwrap();
+    fs::write(directory.join("gradlew"), "").unwrap();
+    for dir in ["build", ".gradle", "app/build", "app/.cxx", "lib/core/build", "modules/custom/build", "other/build"] {
+        fs::create_dir_all(directory.join(dir)).unwrap();
+    }
+    fs::write(directory.join("app/build.gradle.kts"), "").unwrap();
+
+    let matches: Vec<_> = scan(&directory)
+        .into_iter()
+        .inspect(|data| assert_eq!(data.group(), directory, "subproject wasn't grouped with the root project"))
+        .map(|data| data.path)
+        .collect();
+    let expected = [".gradle", "app/.cxx", "app/build", "build", "lib/core/build", "modules/custom/build"];
+    assert_eq!(matches, expected.map(|dir| directory.join(dir)));
+
+    fs::remove_dir_all(directory).unwrap();
+}
+
+#[test]
+fn dotnet_output_directories() {
+    let directory = test_directory("dotnet");
+    let props = r"<Project><PropertyGroup>
+        <BaseIntermediateOutputPath>$(MSBuildThisFileDirectory)artifacts\obj\$(MSBuildProjectName)\</BaseIntermediateOutputPath>
+    </PropertyGroup></Project>";
+    fs::write(directory.join("Directory.Build.props"), props).unwrap();
+    fs::write(directory.join("App.sln"), "").unwrap();
+    for dir in ["A", "B", "C/src", ".vs", "A/bin", "A/obj", "B/out/Debug", "artifacts/obj/A", "artifacts/obj/B"] {
+        fs::create_dir_all(directory.join(dir)).unwrap();
+    }
+    fs::write(directory.join("artifacts/obj/A/project.assets.json"), "{}").unwrap();
+    fs::write(directory.join("artifacts/obj/B/B.fsproj.nuget.g.props"), "").unwrap();
+    fs::write(directory.join("A/A.csproj"), "<Project></Project>").unwrap();
+    let project = "<Project><PropertyGroup><BaseOutputPath>out/</BaseOutputPath></PropertyGroup></Project>";
+    fs::write(directory.join("B/B.fsproj"), project).unwrap();
+    // Overrides pointing at directories not created by MSBuild are ignored
+    let project = r"<Project><PropertyGroup>
+        <BaseOutputPath>src</BaseOutputPath>
+        <BaseIntermediateOutputPath>../A</BaseIntermediateOutputPath>
+    </PropertyGroup></Project>";
+    fs::write(directory.join("C/C.csproj"), project).unwrap();
+
+    let matches = scan_paths(&directory);
+    let expected = [".vs", "A/bin", "B/out", "artifacts/obj/A", "artifacts/obj/B"];
+    assert_eq!(matches, expected.map(|dir| directory.join(dir)));
+
+    fs::remove_dir_all(directory).unwrap();
+}
+
+#[test]
+fn go_vendor_and_executables() {
+    let directory = test_directory("go");
+    fs::write(directory.join("go.mod"), "module example.com/go\n").unwrap();
+    for dir in ["vendor/example.com/dep", "bin", "scripts/bin"] {
+        fs::create_dir_all(directory.join(dir)).unwrap();
+    }
+    fs::write(directory.join("vendor/modules.txt"), "# example.com/dep v1.0.0\n").unwrap();
+    fs::write(directory.join("bin/app"), b"\x7fELF\x02\x01\x01").unwrap();
+    fs::write(directory.join("bin/app.exe"), b"MZ\x90\x00").unwrap();
+    fs::write(directory.join("scripts/go.mod"), "module example.com/scripts\n").unwrap();
+    fs::write(directory.join("scripts/bin/run.sh"), "#!/bin/sh\n").unwrap();
+
+    let matches = scan_paths(&directory);
+    assert_eq!(matches, [directory.join("bin"), directory.join("vendor")]);
+
+    fs::remove_dir_all(directory).unwrap();
+}
+
+#[test]
+fn go_caches_used_by_projects() {
+    let directory = test_directory("go_caches");
+    let (build_cache, module_cache) = (directory.join("cache/go-build"), directory.join("go/pkg/mod"));
+    for dir in [&build_cache, &module_cache, &directory.join("projects/app")] {
+        fs::create_dir_all(dir).unwrap();
+    }
+    fs::write(directory.join("projects/app/go.mod"), "module example.com/app\n").unwrap();
+    std::env::set_var("GOCACHE", &build_cache);
+    std::env::set_var("GOMODCACHE", &module_cache);
+
+    // Global caches are only reported when explicitly enabled, e.g. by --global-caches
+    let matches = scan(&directory.join("projects"));
+    assert!(matches.is_empty(), "global caches were found by the default heuristics: {:#?}", matches);
+
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let mut scanner = Scanner::new(&directory.join("projects"), sender);
+    scanner.heuristics.extend(GLOBAL_CACHE_HEURISTICS);
+    scanner.scan();
+    let mut matches: Vec<_> = receiver.iter().collect();
+    matches.sort_by(|a, b| a.path.cmp(&b.path));
+    let paths: Vec<_> = matches.iter().map(|data| data.path.as_path()).collect();
+    assert_eq!(paths, [build_cache.as_path(), module_cache.as_path()]);
+    for data in &matches {
+        assert_eq!(data.group(), data.path, "shared cache wasn't grouped on its own");
+        assert!(data.outside_root(), "shared cache wasn't flagged as outside of the scan");
+    }
+
+    fs::remove_dir_all(directory).unwrap();
+}
+
+#[test]
+#[cfg(unix)]
+fn bazel_output_base_and_symlinks() {
+    let directory = test_directory("bazel");
+    let (workspace, output) = (directory.join("workspace"), directory.join("output_base/execroot/_main/bazel-out"));
+    fs::create_dir_all(&workspace).unwrap();
+    fs::create_dir_all(output.join("k8-fastbuild/bin")).unwrap();
+    fs::write(workspace.join("MODULE.bazel"), "").unwrap();
+    std::os::unix::fs::symlink(&output, workspace.join("bazel-out")).unwrap();
+    std::os::unix::fs::symlink(output.join("k8-fastbuild/bin"), workspace.join("bazel-bin")).unwrap();
+
+    let matches = scan(&workspace);
+    let paths: Vec<_> = matches.iter().map(|data| data.path.clone()).collect();
+    assert_eq!(paths, [output.canonicalize().unwrap(), workspace.join("bazel-bin"), workspace.join("bazel-out")]);
+    let comment = &matches[0].languages().last().unwrap().comment;
+    assert!(matches[0].outside_root(), "output base wasn't flagged as outside of the scan");
+    assert!(comment.contains("outside"), "output base wasn't described as outside of the scan: {comment}");
+
+    fs::remove_dir_all(directory).unwrap();
+}
+
+#[test]
+fn javascript_tool_directories() {
+    let directory = test_directory("javascript");
+    for file in ["package.json", "next.config.mjs", "vitest.config.ts", "angular.json", ".yarnrc.yml"] {
+        fs::write(directory.join(file), "").unwrap();
+    }
+    fs::write(directory.join(".gitignore"), ".yarn/*\n!.yarn/releases\n").unwrap();
+    for dir in [".next", "coverage", ".angular/cache", ".yarn/cache", ".yarn/releases", ".turbo", "storybook-static"] {
+        fs::create_dir_all(directory.join(dir)).unwrap();
+    }
+    // Ignoring the whole .yarn directory ignores the cache as well
+    let yarn = directory.join("yarn");
+    fs::create_dir_all(yarn.join(".yarn/cache")).unwrap();
+    for (file, contents) in [("package.json", ""), (".yarnrc.yml", ""), (".gitignore", "/.yarn\n")] {
+        fs::write(yarn.join(file), contents).unwrap();
+    }
+
+    let matches = scan_paths(&directory);
+    let expected = [".angular/cache", ".next", ".yarn/cache", "coverage", "yarn/.yarn/cache"];
+    assert_eq!(matches, expected.map(|dir| directory.join(dir)));
+
+    fs::remove_dir_all(directory).unwrap();
+}
+
+#[test]
+fn python_caches_and_distributables() {
+    let directory = test_directory("python");
+    fs::write(directory.join("pyproject.toml"), "[project]\nname = \"example\"\n").unwrap();
+    for dir in [".pytest_cache", ".tox/py312", "src/example.egg-info", "build/lib", "dist", "docs/build"] {
+        fs::create_dir_all(directory.join(dir)).unwrap();
+    }
+    fs::write(directory.join("dist/example-1.0-py3-none-any.whl"), "").unwrap();
+
+    let matches = scan_paths(&directory);
+    let expected = [".pytest_cache", ".tox", "build", "dist", "src/example.egg-info"];
+    assert_eq!(matches, expected.map(|dir| directory.join(dir)));
+
+    fs::remove_dir_all(directory).unwrap();
+}
+
+#[test]
+fn virtual_environments_by_content() {
+    let directory = test_directory("venv");
+    for dir in ["venv", "env", ".venv", "unknown", "conda/conda-meta", "miniconda/conda-meta", "miniconda/condabin"] {
+        fs::create_dir_all(directory.join(dir)).unwrap();
+    }
+    fs::write(directory.join("env/pyvenv.cfg"), "home = /usr/bin\nversion = 3.12.1\n").unwrap();
+    fs::write(directory.join(".venv/pyvenv.cfg"), "home = /usr/bin\nversion_info = 3.11.7.final.0\n").unwrap();
+    fs::write(directory.join("unknown/pyvenv.cfg"), "home = /usr/bin\nversion = unknown\n").unwrap();
+    fs::write(directory.join("conda/conda-meta/python-3.10.13-h955ad1f_0.json"), "{}").unwrap();
+
+    let matches: Vec<_> = scan(&directory)
+        .into_iter()
+        .map(|data| (data.path.clone(), data.languages().last().unwrap().comment.clone()))
+        .collect();
+    assert_eq!(matches, [
+        (directory.join(".venv"), "Found Python 3.11.7 virtual environment.".to_owned()),
+        (directory.join("conda"), "Found conda environment with Python 3.10.13.".to_owned()),
+        (directory.join("env"), "Found Python 3.12.1 virtual environment.".to_owned()),
+        (directory.join("unknown"), "Found Python virtual environment.".to_owned()),
+    ]);
+
+    fs::remove_dir_all(directory).unwrap();
+}
+
+#[test]
+fn functional_language_build_directories() {
+    let directory = test_directory("functional");
+    let projects = [
+        ("stack", "stack.yaml", ".stack-work"),
+        ("cabal", "example.cabal", "dist-newstyle"),
+        ("dune", "dune-project", "_build"),
+        ("dune", "dune-project", "_opam/.opam-switch"),
+        ("rebar", "rebar.config", "_build"),
+        ("mix", "mix.exs", "deps"),
+        ("mix", "mix.exs", ".elixir_ls"),
+        ("unknown", "README.md", "_build"),
+    ];
+    for (project, file, dir) in projects {
+        fs::create_dir_all(directory.join(project).join(dir)).unwrap();
+        fs::write(directory.join(project).join(file), "").unwrap();
+    }
+
+    let matches = scan_paths(&directory);
+    let expected = [
+        "cabal/dist-newstyle",
+        "dune/_build",
+        "dune/_opam",
+        "mix/.elixir_ls",
+        "mix/deps",
+        "rebar/_build",
+        "stack/.stack-work",
+    ];
+    assert_eq!(matches, expected.map(|dir| directory.join(dir)));
+
+    fs::remove_dir_all(directory).unwrap();
+}
+
+#[test]
+fn native_build_systems() {
+    let directory = test_directory("native");
+    let root = directory.join("root");
+    let projects = [
+        ("zig", "build.zig", ".zig-cache"),
+        ("zig", "build.zig", "zig-out"),
+        ("swift", "Package.swift", ".build"),
+        ("meson", "meson.build", "builddir/meson-private"),
+        ("autotools", "configure", "autom4te.cache"),
+        ("autotools", "config.status", "src"),
+        ("buck", ".buckconfig", "buck-out"),
+        ("bazel", "MODULE.bazel", "src"),
+    ];
+    for (project, file, dir) in projects {
+        fs::create_dir_all(root.join(project).join(dir)).unwrap();
+        fs::write(root.join(project).join(file), "").unwrap();
+    }
+    let mut expected: Vec<_> = [
+        "zig/.zig-cache",
+        "zig/zig-out",
+        "swift/.build",
+        "meson/builddir",
+        "autotools/autom4te.cache",
+        "autotools/config.status",
+        "buck/buck-out",
+    ]
+    .into_iter()
+    .map(|path| root.join(path))
+    .collect();
+    #[cfg(unix)]
+    {
+        let output = directory.join("output_base/execroot/_main/bazel-out");
+        fs::create_dir_all(&output).unwrap();
+        std::os::unix::fs::symlink(&output, root.join("bazel/bazel-out")).unwrap();
+        expected.extend([output, root.join("bazel/bazel-out")]);
+    }
+
+    let matches = scan_paths(&root);
+    expected.sort();
+    assert_eq!(matches, expected);
+
+    fs::remove_dir_all(directory).unwrap();
+}
+
+#[test]
+fn cmake_build_trees() {
+    let directory = test_directory("cmake");
+    let root = directory.join("root");
+    let app = root.join("app");
+    let cache = |build: &Path, source: &Path| {
+        fs::create_dir_all(build).unwrap();
+        let cache = format!("CMAKE_BUILD_TYPE:STRING=Debug\nCMAKE_HOME_DIRECTORY:INTERNAL={}\n", source.display());
+        fs::write(build.join("CMakeCache.txt"), cache).unwrap();
+    };
+    fs::create_dir_all(app.join("rebuild-notes")).unwrap();
+    fs::create_dir_all(app.join(".cache/clangd/index")).unwrap();
+    fs::write(app.join("CMakeLists.txt"), "").unwrap();
+    let presets = r#"{"version": 3, "configurePresets": [
+        {"name": "base", "hidden": true, "binaryDir": "${sourceParentDir}/../presets/${presetName}"},
+        {"name": "debug", "inherits": "base"}
+    ]}"#;
+    fs::write(app.join("CMakePresets.json"), presets).unwrap();
+    cache(&app.join("out"), &app);
+    cache(&root.join("app-build"), &app);
+    cache(&directory.join("presets/debug"), &app);
+    cache(&root.join("in-source"), &root.join("in-source"));
+    fs::create_dir_all(root.join("in-source/CMakeFiles")).unwrap();
+
+    let matches: Vec<_> = scan(&root).into_iter().map(|data| (data.path.clone(), data.group().to_owned())).collect();
+    let mut expected = vec![
+        (app.join(".cache/clangd"), app.clone()),
+        (app.join("out"), app.clone()),
+        (root.join("app-build"), app.clone()),
+        (directory.join("presets/debug"), app.clone()),
+        (root.join("in-source/CMakeFiles"), root.join("in-source")),
+    ];
+    expected.sort();
+    assert_eq!(matches, expected);
+
+    fs::remove_dir_all(directory).unwrap();
+}
+
+#[test]
+fn game_engine_projects() {
+    let directory = test_directory("engines");
+    let unity = directory.join("unity");
+    for dir in ["Assets", "Packages", "ProjectSettings", "library", "Temp", "UserSettings", "Builds"] {
+        fs::create_dir_all(unity.join(dir)).unwrap();
+    }
+    let version = "m_EditorVersion: 2022.3.10f1\nm_EditorVersionWithRevision: 2022.3.10f1 (ff3792e53c62)\n";
+    fs::write(unity.join("ProjectSettings/ProjectVersion.txt"), version).unwrap();
+    let unreal = directory.join("unreal");
+    let dirs = ["Binaries", "Intermediate", "Saved", "Content", "Plugins/Tool/Binaries", "Plugins/Tool/Saved"];
+    // Plugins without sources are prebuilt
+    let plugin_dirs = ["Plugins/Tool/Source", "Plugins/Prebuilt/Binaries"];
+    for dir in dirs.into_iter().chain(plugin_dirs) {
+        fs::create_dir_all(unreal.join(dir)).unwrap();
+    }
+    fs::write(unreal.join("Game.uproject"), "{}").unwrap();
+    fs::write(unreal.join("Plugins/Tool/Tool.uplugin"), "{}").unwrap();
+    fs::write(unreal.join("Plugins/Prebuilt/Prebuilt.uplugin"), "{}").unwrap();
+    let godot = directory.join("godot");
+    fs::create_dir_all(godot.join(".godot")).unwrap();
+    fs::write(godot.join("project.godot"), "config_version=5\n").unwrap();
+
+    let matches = scan(&directory);
+    let paths: Vec<_> = matches.iter().map(|data| data.path.strip_prefix(&directory).unwrap()).collect();
+    let expected = [
+        "godot/.godot",
+        "unity/Temp",
+        "unity/UserSettings",
+        "unity/library",
+        "unreal/Binaries",
+        "unreal/Intermediate",
+        "unreal/Plugins/Tool/Binaries",
+        "unreal/Saved",
+    ];
+    assert_eq!(paths, expected.map(Path::new));
+    let comment = &matches[1].languages().last().unwrap().comment;
+    assert_eq!(comment, "Found Unity 2022.3.10f1 project and Temp directory.");
+
+    fs::remove_dir_all(directory).unwrap();
+}
+
+#[test]
+fn installed_dependencies() {
+    let directory = test_directory("dependencies");
+    let dirs = [
+        "ruby/vendor/bundle/ruby/3.3.0",
+        "ruby/.bundle",
+        "ruby/.gems/ruby/3.3.0",
+        "ruby-local/.bundle/ruby/3.3.0",
+        "php/lib/composer",
+        "php/vendor/composer",
+        "php-manual/vendor/library",
+        "ios/Pods",
+        "ios/App.xcodeproj",
+        "ios/DerivedData",
+        "ios/Carthage/Build",
+        "ios/Carthage/Checkouts",
+    ];
+    for dir in dirs {
+        fs::create_dir_all(directory.join(dir)).unwrap();
+    }
+    let files = [
+        ("ruby/Gemfile.lock", ""),
+        ("ruby/.bundle/config", "BUNDLE_PATH: \".gems\"\n"),
+        ("ruby-local/Gemfile.lock", ""),
+        ("php/composer.json", r#"{"config": {"vendor-dir": "lib"}}"#),
+        ("php/composer.lock", "{}"),
+        ("php/lib/autoload.php", ""),
+        ("php/vendor/autoload.php", ""),
+        ("php-manual/composer.json", "{}"),
+        ("php-manual/composer.lock", "{}"),
+        ("ios/Podfile.lock", ""),
+        ("ios/Pods/Manifest.lock", ""),
+        ("ios/Cartfile.resolved", ""),
+    ];
+    for (file, contents) in files {
+        fs::write(directory.join(file), contents).unwrap();
+    }
+
+    let matches = scan_paths(&directory);
+    let paths: Vec<_> = matches.iter().map(|path| path.strip_prefix(&directory).unwrap()).collect();
+    let expected = [
+        "ios/Carthage/Build",
+        "ios/DerivedData",
+        "ios/Pods",
+        "php/lib",
+        "ruby/.gems",
+        "ruby/vendor/bundle",
+        "ruby-local/.bundle",
+    ];
+    assert_eq!(paths, expected.map(Path::new));
+
+    fs::remove_dir_all(directory).unwrap();
+}
+
+#[test]
+fn path_matches_are_not_scanned() {
+    let directory = test_directory("nested_path_match");
+    let gem = directory.join("vendor/bundle/ruby/3.3.0/gems/execjs-2.9.1");
+    fs::create_dir_all(gem.join("node_modules")).unwrap();
+    fs::create_dir_all(gem.join("__pycache__")).unwrap();
+    fs::write(gem.join("package.json"), "{}").unwrap();
+    fs::write(directory.join("Gemfile.lock"), "").unwrap();
+
+    let matches = scan_paths(&directory);
+    assert_eq!(matches, [directory.join("vendor/bundle")]);
+
+    fs::remove_dir_all(directory).unwrap();
+}
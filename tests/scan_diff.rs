@@ -0,0 +1,44 @@
+use project_cleaner::core::{DirStats, MatchSummary, ScanDiff, SizeKind};
+use size::Size;
+use std::path::PathBuf;
+
+fn summary(path: &str, languages: &[&str], size: i64) -> MatchSummary {
+    MatchSummary {
+        path: PathBuf::from(path),
+        languages: languages.iter().map(|lang| lang.to_string()).collect(),
+        stats: Some(DirStats {
+            disk_size: Some(Size::from_bytes(size)),
+            ..DirStats::default()
+        }),
+    }
+}
+
+#[test]
+fn new_removed_and_grown_matches() {
+    let previous = [
+        summary("/a/target", &["Rust"], 1000),
+        summary("/b/target", &["Rust"], 1000),
+        summary("/c/node_modules", &["JavaScript"], 500),
+        summary("/d/build", &["Gradle", "CMake"], 100),
+    ];
+    let current = [
+        summary("/a/target", &["Rust"], 1500),
+        summary("/b/target", &["Rust"], 4000),
+        summary("/d/build", &["Gradle", "CMake"], 50),
+        summary("/e/node_modules", &["JavaScript"], 700),
+    ];
+    let diff = ScanDiff::new(&previous, &current, SizeKind::Disk);
+
+    let rust = &diff.languages["Rust"];
+    assert!(rust.new.is_empty() && rust.removed.is_empty());
+    let grown: Vec<_> = rust.grown.iter().map(|growth| (growth.path.to_str().unwrap(), growth.bytes())).collect();
+    assert_eq!(grown, [("/b/target", 3000), ("/a/target", 500)], "growth wasn't sorted by bytes");
+    assert_eq!(rust.grown[1].percent(), Some(50.0));
+
+    let js = &diff.languages["JavaScript"];
+    assert_eq!(js.new, [current[3].clone()]);
+    assert_eq!(js.removed, [previous[2].clone()]);
+    assert!(!diff.languages.contains_key("Gradle"), "shrunk match was reported");
+
+    assert!(ScanDiff::new(&current, &current, SizeKind::Disk).is_empty());
+}
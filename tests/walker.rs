@@ -23,3 +23,146 @@ fn current_project_directory_walk() {
     });
     handle.join().unwrap();
 }
+
+#[test]
+fn current_project_stats_during_scan() {
+    let directory = std::env::current_dir().unwrap();
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let (stats_sender, stats_receiver) = std::sync::mpsc::channel();
+
+    let mut scanner = Scanner::new(&directory, sender);
+    scanner.stats_sender = Some(stats_sender);
+    scanner.scan();
+
+    let matches: Vec<_> = receiver.iter().map(|data| data.path).collect();
+    // Iteration ends only after the stats pool drops all of its senders
+    let stats: Vec<_> = stats_receiver.iter().collect();
+    assert_eq!(matches.len(), stats.len(), "stats were not calculated for every match");
+    for (path, stats) in stats {
+        println!("Stats for {}: {:?}", path.display(), stats);
+        assert!(matches.contains(&path), "stats were sent for an unknown path");
+        assert!(
+            stats.disk_size.is_some() && stats.apparent_size.is_some(),
+            "size of {} wasn't calculated",
+            path.display()
+        );
+    }
+}
+
+#[test]
+fn incremental_scan_with_index() {
+    let root = std::env::temp_dir().join(format!("project_cleaner_index_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&root);
+    for project in ["first", "second"] {
+        std::fs::create_dir_all(root.join("projects").join(project).join("node_modules")).unwrap();
+        std::fs::write(root.join("projects").join(project).join("package.json"), "{}").unwrap();
+    }
+    let index_path = root.join("index.json");
+
+    let scan = || {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let (stats_sender, stats_receiver) = std::sync::mpsc::channel();
+        let mut scanner = Scanner::new(&root.join("projects"), sender);
+        scanner.index_path = Some(index_path.clone());
+        scanner.stats_sender = Some(stats_sender);
+        scanner.scan();
+        let mut matches: Vec<_> = receiver.iter().map(|data| (data.languages().len(), data.path)).collect();
+        matches.sort_by(|a, b| a.1.cmp(&b.1));
+        // The index is saved after all the stats are calculated
+        assert_eq!(stats_receiver.iter().count(), matches.len(), "stats were not calculated for every match");
+        matches
+    };
+
+    let first = scan();
+    assert_eq!(first.len(), 2, "node_modules directories weren't detected");
+    assert!(index_path.exists(), "index wasn't saved");
+    assert_eq!(scan(), first, "matches loaded from the index are different");
+
+    std::fs::remove_file(root.join("projects").join("second").join("package.json")).unwrap();
+    std::fs::create_dir_all(root.join("projects").join("third").join("node_modules")).unwrap();
+    std::fs::write(root.join("projects").join("third").join("package.json"), "{}").unwrap();
+    let paths: Vec<_> = scan().into_iter().map(|(_, path)| path).collect();
+    assert_eq!(
+        paths,
+        ["first", "third"].map(|project| root.join("projects").join(project).join("node_modules")),
+        "changes since the previous scan were not detected"
+    );
+
+    // Removing the marker file only modifies the matched directory, not the one containing it
+    let venv = root.join("projects").join("first").join("env");
+    std::fs::create_dir_all(&venv).unwrap();
+    std::fs::write(venv.join("pyvenv.cfg"), "version = 3.12.1\n").unwrap();
+    assert!(scan().iter().any(|(_, path)| *path == venv), "virtual environment wasn't detected");
+    scan();
+    std::fs::remove_file(venv.join("pyvenv.cfg")).unwrap();
+    assert!(scan().iter().all(|(_, path)| *path != venv), "changes inside of a match were not detected");
+
+    // Partial matches inside of other matches are restored with the directory containing them
+    let rust = root.join("projects").join("rust");
+    std::fs::create_dir_all(rust.join("target").join("debug").join(".fingerprint")).unwrap();
+    std::fs::create_dir_all(rust.join("target").join("debug").join("incremental")).unwrap();
+    std::fs::write(rust.join("Cargo.toml"), "[package]\nname = \"rust\"\n").unwrap();
+    let found = scan();
+    let incremental = rust.join("target").join("debug").join("incremental");
+    assert!(found.iter().any(|(_, path)| *path == incremental), "incremental cache wasn't detected");
+    assert_eq!(scan(), found, "partial matches loaded from the index are different");
+
+    // Editing a file read by the heuristics in place does not modify the directory containing it
+    let configured = root.join("projects").join("configured");
+    std::fs::create_dir_all(configured.join(".cargo")).unwrap();
+    for target in ["build", "out"] {
+        std::fs::create_dir_all(configured.join(target)).unwrap();
+        std::fs::write(configured.join(target).join("CACHEDIR.TAG"), "Signature: 8a477f597d28d172789f06886806bc55")
+            .unwrap();
+    }
+    std::fs::write(configured.join("Cargo.toml"), "[package]\nname = \"configured\"\n").unwrap();
+    let config = configured.join(".cargo").join("config.toml");
+    std::fs::write(&config, "build.target-dir = \"build\"\n").unwrap();
+    assert!(scan().iter().any(|(_, path)| *path == configured.join("build")), "configured target wasn't detected");
+    scan();
+    std::fs::write(&config, "build.target-dir = \"out\"\n").unwrap();
+    let paths: Vec<_> = scan().into_iter().map(|(_, path)| path).collect();
+    assert!(
+        paths.contains(&configured.join("out")) && !paths.contains(&configured.join("build")),
+        "changes of files read by the heuristics were not detected"
+    );
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn resume_scan_stopped_by_time_limit() {
+    let root = std::env::temp_dir().join(format!("project_cleaner_checkpoint_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&root);
+    for project in ["first", "second", "third"] {
+        std::fs::create_dir_all(root.join("projects").join(project).join("node_modules")).unwrap();
+        std::fs::write(root.join("projects").join(project).join("package.json"), "{}").unwrap();
+    }
+    let checkpoint_path = root.join("checkpoint.json");
+
+    let scan = |time_limit, resume| {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let mut scanner = Scanner::new(&root.join("projects"), sender);
+        scanner.time_limit = time_limit;
+        scanner.checkpoint_path = Some(checkpoint_path.clone());
+        scanner.resume = resume;
+        scanner.scan();
+        let mut matches: Vec<_> = receiver.iter().map(|data| data.path).collect();
+        matches.sort();
+        matches
+    };
+
+    let stopped = scan(Some(std::time::Duration::ZERO), false);
+    assert!(stopped.is_empty(), "scan wasn't stopped by the time limit");
+    assert!(checkpoint_path.exists(), "checkpoint wasn't saved");
+
+    let resumed = scan(None, true);
+    assert_eq!(
+        resumed,
+        ["first", "second", "third"].map(|project| root.join("projects").join(project).join("node_modules")),
+        "resumed scan didn't find all the matches"
+    );
+    assert!(!checkpoint_path.exists(), "checkpoint wasn't removed after the scan was completed");
+
+    std::fs::remove_dir_all(root).unwrap();
+}